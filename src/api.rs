@@ -0,0 +1,480 @@
+use std::{
+    io::Read,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Local};
+use iced::{
+    futures::{channel::mpsc, executor::block_on, never::Never, SinkExt as _},
+    Command,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::{
+    message::Message,
+    player::AccountStatus,
+    server::{CrawlingStatus, ServerIdent},
+    AttackTarget, Helper,
+};
+
+/// A request to the local api, that has already been authenticated. The
+/// request is answered by the ui thread, because that owns all the state
+#[derive(Debug, Clone)]
+pub struct ApiCall {
+    pub route: ApiRoute,
+    responder: Responder,
+}
+
+#[derive(Debug, Clone)]
+pub enum ApiRoute {
+    Status,
+    Metrics,
+    Action(ApiAction),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ApiAction {
+    AutoBattle {
+        server: String,
+        account: String,
+        enabled: bool,
+    },
+    AutoLure {
+        server: String,
+        account: String,
+        enabled: bool,
+    },
+    StartCrawling {
+        server: String,
+        threads: Option<usize>,
+    },
+    StopCrawling {
+        server: String,
+    },
+    SaveHof {
+        server: String,
+    },
+    Attack {
+        server: String,
+        account: String,
+        target: String,
+    },
+}
+
+/// Wraps the request, so that it can be send around in a message. The
+/// request can only be answered once
+#[derive(Clone)]
+struct Responder(Arc<Mutex<Option<Request>>>);
+
+impl std::fmt::Debug for Responder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Responder")
+    }
+}
+
+impl Responder {
+    fn respond(&self, code: u16, body: &impl Serialize) {
+        let body = serde_json::to_string(body).unwrap_or_default();
+        self.respond_raw(code, body, "application/json");
+    }
+
+    fn respond_raw(&self, code: u16, body: String, content_type: &str) {
+        let Some(request) = self.0.lock().unwrap().take() else {
+            return;
+        };
+        respond_raw(request, code, body, content_type);
+    }
+}
+
+fn respond(request: Request, code: u16, body: &impl Serialize) {
+    let body = serde_json::to_string(body).unwrap_or_default();
+    respond_raw(request, code, body, "application/json");
+}
+
+fn respond_raw(request: Request, code: u16, body: String, content_type: &str) {
+    let mut response = Response::from_string(body).with_status_code(code);
+    if let Ok(header) =
+        Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes())
+    {
+        response = response.with_header(header);
+    }
+    if let Err(e) = request.respond(response) {
+        warn!("Could not answer api request: {e}");
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ApiError<'a> {
+    error: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct ApiOk {
+    ok: bool,
+}
+
+/// Stops the http server, once the subscription gets dropped
+struct ServerGuard(Arc<Server>);
+
+impl Drop for ServerGuard {
+    fn drop(&mut self) {
+        self.0.unblock();
+    }
+}
+
+/// Serves the api on localhost until the subscription is dropped. Every
+/// authenticated request gets forwarded to the ui as a message
+pub async fn serve(
+    port: u16,
+    token: String,
+    output: mpsc::Sender<Message>,
+) -> Never {
+    let server = match Server::http(("127.0.0.1", port)) {
+        Ok(server) => Arc::new(server),
+        Err(e) => {
+            warn!("Could not start the api on port {port}: {e}");
+            return std::future::pending().await;
+        }
+    };
+    info!("Api listening on 127.0.0.1:{port}");
+
+    let _guard = ServerGuard(server.clone());
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let Some(call) = parse_request(request, &token) else {
+                continue;
+            };
+            let mut output = output.clone();
+            if block_on(output.send(Message::ApiCall(call))).is_err() {
+                break;
+            }
+        }
+    });
+
+    std::future::pending().await
+}
+
+/// Checks the token & parses the request. Invalid requests are answered
+/// immediately
+fn parse_request(mut request: Request, token: &str) -> Option<ApiCall> {
+    let authorized = request.headers().iter().any(|a| {
+        a.field.equiv("Authorization")
+            && a.value
+                .as_str()
+                .strip_prefix("Bearer ")
+                .is_some_and(|given| token_matches(given, token))
+    });
+    if token.is_empty() || !authorized {
+        respond(
+            request,
+            401,
+            &ApiError {
+                error: "Invalid token",
+            },
+        );
+        return None;
+    }
+
+    let route = match (request.method().as_str(), request.url()) {
+        ("GET", "/status") => ApiRoute::Status,
+        ("GET", "/metrics") => ApiRoute::Metrics,
+        ("POST", "/action") => {
+            let mut body = String::new();
+            if request.as_reader().read_to_string(&mut body).is_err() {
+                respond(
+                    request,
+                    400,
+                    &ApiError {
+                        error: "Could not read body",
+                    },
+                );
+                return None;
+            }
+            match serde_json::from_str(&body) {
+                Ok(action) => ApiRoute::Action(action),
+                Err(e) => {
+                    respond(
+                        request,
+                        400,
+                        &ApiError {
+                            error: &e.to_string(),
+                        },
+                    );
+                    return None;
+                }
+            }
+        }
+        _ => {
+            respond(
+                request,
+                404,
+                &ApiError {
+                    error: "Unknown route",
+                },
+            );
+            return None;
+        }
+    };
+
+    Some(ApiCall {
+        route,
+        responder: Responder(Arc::new(Mutex::new(Some(request)))),
+    })
+}
+
+/// Compares the tokens in constant time, so that the token can not be guessed
+/// byte by byte from the response times
+fn token_matches(given: &str, token: &str) -> bool {
+    let (given, token) = (given.as_bytes(), token.as_bytes());
+    given.len() == token.len()
+        && given.iter().zip(token).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Why an action could not be done
+enum ActionError {
+    /// The request does not make sense, like an unknown account
+    BadRequest(&'static str),
+    /// The request is fine, but the helper is not in a state to do it
+    Conflict(&'static str),
+}
+
+impl ActionError {
+    fn status(&self) -> u16 {
+        match self {
+            ActionError::BadRequest(_) => 400,
+            ActionError::Conflict(_) => 409,
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            ActionError::BadRequest(e) | ActionError::Conflict(e) => e,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StatusResponse {
+    accounts: Vec<AccountState>,
+    servers: Vec<ServerState>,
+}
+
+#[derive(Debug, Serialize)]
+struct AccountState {
+    name: String,
+    server: String,
+    status: String,
+    next_free_fight: Option<DateTime<Local>>,
+    scrapbook_items: Option<usize>,
+    auto_battle: Option<bool>,
+    auto_lure: Option<bool>,
+    lured_today: Option<u16>,
+}
+
+#[derive(Debug, Serialize)]
+struct ServerState {
+    server: String,
+    crawling: &'static str,
+    threads: usize,
+    crawled: usize,
+    remaining: usize,
+}
+
+impl Helper {
+    pub fn handle_api_call(&mut self, call: ApiCall) -> Command<Message> {
+        let responder = call.responder;
+        match call.route {
+            ApiRoute::Status => {
+                responder.respond(200, &self.api_status());
+                Command::none()
+            }
+            ApiRoute::Metrics if !self.config.api.metrics => {
+                responder.respond(
+                    404,
+                    &ApiError {
+                        error: "Metrics are disabled",
+                    },
+                );
+                Command::none()
+            }
+            ApiRoute::Metrics => {
+                let body = self.metrics.render(&self.servers);
+                responder.respond_raw(200, body, "text/plain; version=0.0.4");
+                Command::none()
+            }
+            ApiRoute::Action(action) => match self.api_action(action) {
+                Ok(msg) => {
+                    responder.respond(200, &ApiOk { ok: true });
+                    self.handle_msg(msg)
+                }
+                Err(error) => {
+                    responder.respond(
+                        error.status(),
+                        &ApiError {
+                            error: error.message(),
+                        },
+                    );
+                    Command::none()
+                }
+            },
+        }
+    }
+
+    fn api_status(&self) -> StatusResponse {
+        let mut accounts = vec![];
+        let mut servers = vec![];
+        for server in self.servers.0.values() {
+            for acc in server.accounts.values() {
+                let mut next_free_fight = None;
+                let status = match &*acc.status.lock().unwrap() {
+                    AccountStatus::LoggingIn => "Logging in".to_string(),
+                    AccountStatus::Idle(_, gs) => {
+                        next_free_fight = gs.arena.next_free_fight;
+                        "Active".to_string()
+                    }
+                    AccountStatus::Busy(gs, reason) => {
+                        next_free_fight = gs.arena.next_free_fight;
+                        reason.to_string()
+                    }
+                    AccountStatus::FatalError(e) => format!("Error: {e}"),
+                    AccountStatus::LoggingInAgain => "Logging in".to_string(),
+                };
+                accounts.push(AccountState {
+                    name: acc.name.clone(),
+                    server: server.ident.url.clone(),
+                    status,
+                    next_free_fight,
+                    scrapbook_items: acc
+                        .scrapbook_info
+                        .as_ref()
+                        .map(|a| a.scrapbook.items.len()),
+                    auto_battle: acc
+                        .scrapbook_info
+                        .as_ref()
+                        .map(|a| a.auto_battle),
+                    auto_lure: acc
+                        .underworld_info
+                        .as_ref()
+                        .map(|a| a.auto_lure),
+                    lured_today: acc
+                        .underworld_info
+                        .as_ref()
+                        .map(|a| a.underworld.lured_today),
+                });
+            }
+
+            let (crawling, threads, crawled, remaining) = match &server.crawling
+            {
+                CrawlingStatus::Waiting => ("waiting", 0, 0, 0),
+                CrawlingStatus::Restoring => ("restoring", 0, 0, 0),
+                CrawlingStatus::CrawlingFailed(_) => ("failed", 0, 0, 0),
+                CrawlingStatus::Crawling {
+                    threads,
+                    que,
+                    player_info,
+                    ..
+                } => (
+                    "crawling",
+                    *threads,
+                    player_info.len(),
+                    que.lock().unwrap().count_remaining(),
+                ),
+            };
+            servers.push(ServerState {
+                server: server.ident.url.clone(),
+                crawling,
+                threads,
+                crawled,
+                remaining,
+            });
+        }
+        StatusResponse { accounts, servers }
+    }
+
+    /// Converts the action into the message, that the ui would send
+    fn api_action(&self, action: ApiAction) -> Result<Message, ActionError> {
+        use ActionError::{BadRequest, Conflict};
+
+        let find_server = |url: &str| {
+            let id = ServerIdent::new(url).id;
+            self.servers.get(&id).ok_or(BadRequest("Unknown server"))
+        };
+        let find_account = |url: &str, name: &str| {
+            find_server(url)?
+                .accounts
+                .values()
+                .find(|a| a.name.eq_ignore_ascii_case(name))
+                .ok_or(BadRequest("Unknown account"))
+        };
+
+        Ok(match action {
+            ApiAction::AutoBattle {
+                server,
+                account,
+                enabled,
+            } => Message::AutoBattle {
+                ident: find_account(&server, &account)?.ident,
+                state: enabled,
+            },
+            ApiAction::AutoLure {
+                server,
+                account,
+                enabled,
+            } => Message::AutoLure {
+                ident: find_account(&server, &account)?.ident,
+                state: enabled,
+            },
+            ApiAction::StartCrawling { server, threads } => {
+                if threads == Some(0) {
+                    return Err(BadRequest("threads has to be at least 1"));
+                }
+                Message::CrawlerSetThreads {
+                    server: find_server(&server)?.ident.id,
+                    new_count: threads
+                        .unwrap_or(self.config.start_threads)
+                        .max(1),
+                }
+            }
+            ApiAction::StopCrawling { server } => Message::CrawlerSetThreads {
+                server: find_server(&server)?.ident.id,
+                new_count: 0,
+            },
+            ApiAction::SaveHof { server } => {
+                Message::SaveHoF(find_server(&server)?.ident.id)
+            }
+            ApiAction::Attack {
+                server,
+                account,
+                target,
+            } => {
+                let account = find_account(&server, &account)?;
+                let si = account
+                    .scrapbook_info
+                    .as_ref()
+                    .ok_or(Conflict("Account has no scrapbook"))?;
+                let CrawlingStatus::Crawling { player_info, .. } =
+                    &find_server(&server)?.crawling
+                else {
+                    return Err(Conflict("Server is not crawled"));
+                };
+                let info = player_info
+                    .values()
+                    .find(|a| a.name.eq_ignore_ascii_case(&target))
+                    .ok_or(BadRequest("Unknown target"))?;
+                let missing =
+                    info.missing_in(&si.scrapbook.items, si.epic_mode);
+                Message::PlayerAttack {
+                    ident: account.ident,
+                    target: AttackTarget {
+                        missing,
+                        score: missing as f32,
+                        info: info.clone(),
+                    },
+                }
+            }
+        })
+    }
+}
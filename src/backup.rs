@@ -15,13 +15,14 @@ use tokio::{
 };
 
 use crate::{
-    handle_new_char_info, CharacterInfo, CrawlingOrder, CrawlingStatus, QueID,
-    WorkerQue,
+    config::LureRules, handle_new_char_info, CharacterInfo, CrawlingOrder,
+    CrawlingStatus, QueID, WorkerQue,
 };
 
 pub async fn restore_backup(
     backup: Option<Box<ZHofBackup>>,
     total_pages: usize,
+    rules: LureRules,
 ) -> RestoreData {
     if backup.is_none() {
         debug!("Reset crawling progress");
@@ -65,7 +66,7 @@ pub async fn restore_backup(
             yield_now().await;
         }
         handle_new_char_info(
-            char, &mut equipment, &mut player_info, &mut naked,
+            char, &mut equipment, &mut player_info, &mut naked, &rules,
         );
     }
 
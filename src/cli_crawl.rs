@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use log::warn;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::crawler::CrawlingOrder;
+
+#[derive(Debug, clap::Args, Clone)]
+pub struct CrawlOptions {
+    /// Continue from the local backup of a server instead of starting over
+    #[arg(long)]
+    pub resume: bool,
+    /// Ignore players below this level
+    #[arg(long, value_parser = level_limits)]
+    pub min_level: Option<u32>,
+    /// Ignore players above this level
+    #[arg(long, value_parser = level_limits)]
+    pub max_level: Option<u32>,
+    /// The order in which the hall of fame pages are crawled
+    #[arg(long, value_enum)]
+    pub order: Option<CrawlingOrder>,
+    /// Stop crawling a server after this many minutes. The progress is saved,
+    /// so that it can be resumed later
+    #[arg(long)]
+    pub max_minutes: Option<u64>,
+    /// Stop crawling a server after this many requests. The progress is
+    /// saved, so that it can be resumed later
+    #[arg(long)]
+    pub max_requests: Option<u64>,
+    /// Only crawl servers, whose url matches this regex
+    #[arg(long)]
+    pub include: Option<Regex>,
+    /// Skip servers, whose url matches this regex
+    #[arg(long, default_value = r"speed\.sfgame\.net")]
+    pub exclude: Regex,
+    /// Print the progress as json lines to stdout instead of progress bars
+    #[arg(long)]
+    pub json: bool,
+}
+
+fn level_limits(s: &str) -> Result<u32, String> {
+    clap_num::number_range(s, 1, 9999)
+}
+
+impl CrawlOptions {
+    /// Checks the options, that depend on each other
+    pub fn validate(&self) -> Result<(), String> {
+        if let (Some(min), Some(max)) = (self.min_level, self.max_level) {
+            if min > max {
+                return Err(format!(
+                    "--min-level ({min}) must not be above --max-level \
+                     ({max})"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn should_crawl(&self, url: &str) -> bool {
+        self.include.as_ref().map_or(true, |a| a.is_match(url))
+            && !self.exclude.is_match(url)
+    }
+}
+
+/// Reads the server urls from a file with one url per line. Empty lines and
+/// lines starting with `#` are skipped
+pub async fn read_server_list(path: PathBuf) -> Option<Vec<String>> {
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("Could not read the server list from {path:?}: {e}");
+            return None;
+        }
+    };
+    Some(
+        content
+            .lines()
+            .map(|a| a.trim())
+            .filter(|a| !a.is_empty() && !a.starts_with('#'))
+            .map(|a| a.to_string())
+            .collect(),
+    )
+}
+
+/// A single line of the json progress output
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum CrawlEvent<'a> {
+    Started {
+        server: &'a str,
+        resumed: bool,
+    },
+    Progress {
+        server: &'a str,
+        crawled: usize,
+        remaining: usize,
+    },
+    /// The backup could not be used, so the server is crawled from the
+    /// start
+    ResumeFailed {
+        server: &'a str,
+        error: &'a str,
+    },
+    LimitReached {
+        server: &'a str,
+        reason: &'a str,
+    },
+    Finished {
+        server: &'a str,
+        error: Option<&'a str>,
+    },
+    Failed {
+        server: &'a str,
+        error: &'a str,
+    },
+    Done,
+}
+
+impl CrawlEvent<'_> {
+    pub fn print(&self) {
+        if let Ok(line) = serde_json::to_string(self) {
+            println!("{line}");
+        }
+    }
+}
@@ -1,12 +1,103 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
+
+use chrono::Utc;
 use iced::Theme;
+use log::{error, info};
 use num_format::CustomFormat;
 use serde::{Deserialize, Serialize};
-use sf_api::session::PWHash;
+use sf_api::{
+    gamestate::{
+        character::Class, items::EquipmentSlot, unlockables::EquipmentIdent,
+    },
+    session::PWHash,
+    sso::SSOProvider,
+};
+
+use crate::{server::ServerIdent, vault, CharacterInfo, ServerID};
+
+/// The current version of the config schema. Bump this and add a step to
+/// `MIGRATIONS`, whenever the shape of the config changes
+pub const CONFIG_VERSION: u32 = 2;
+const CONFIG_FILE: &str = "helper.toml";
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+/// Set, while the config on disk could not be read and must not be replaced
+static WRITE_BLOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Reads & writes the config from this file instead of the default one
+pub fn set_path(path: PathBuf) {
+    _ = CONFIG_PATH.set(path);
+}
+
+fn config_path() -> &'static Path {
+    CONFIG_PATH
+        .get()
+        .map(|a| a.as_path())
+        .unwrap_or(Path::new(CONFIG_FILE))
+}
+
+/// The path of the config with an additional extension
+fn config_path_with(ext: &str) -> PathBuf {
+    let mut path = config_path().as_os_str().to_owned();
+    path.push(ext);
+    path.into()
+}
+
+/// Upgrades the raw config from the version at its index to the next one
+const MIGRATIONS: [fn(&mut toml::Table); CONFIG_VERSION as usize] =
+    [migrate_v0, migrate_v1];
+
+/// Configs before versioning have the same shape as v1, they only lack the
+/// version itself
+fn migrate_v0(_config: &mut toml::Table) {}
+
+/// v2 added Google & Steam accounts, which v1 can not read. Existing entries
+/// stay the same
+fn migrate_v1(_config: &mut toml::Table) {}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(String),
+    TooNew(u32),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(e) => f.write_fmt(format_args!("{e}")),
+            ConfigError::Parse(e) => f.write_fmt(format_args!("{e}")),
+            ConfigError::TooNew(version) => f.write_fmt(format_args!(
+                "The config has version {version}, but this helper only \
+                 supports up to {CONFIG_VERSION}. Please update"
+            )),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(value: std::io::Error) -> Self {
+        ConfigError::Io(value)
+    }
+}
 
-use crate::{server::ServerIdent, ServerID};
+impl From<toml::de::Error> for ConfigError {
+    fn from(value: toml::de::Error) -> Self {
+        ConfigError::Parse(value.to_string())
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
+    #[serde(default)]
+    pub version: u32,
     pub accounts: Vec<AccountConfig>,
     pub theme: AvailableTheme,
     pub base_name: String,
@@ -23,6 +114,30 @@ pub struct Config {
     pub show_class_icons: bool,
     #[serde(default = "default_blacklist_threshhold")]
     pub blacklist_threshold: usize,
+    #[serde(default)]
+    pub watchlist: Vec<WatchedPlayer>,
+    /// Fetch the target again right before an auto-battle, to make sure the
+    /// target still has the items we expect
+    #[serde(default)]
+    pub verify_targets: bool,
+    /// The amount of minutes, that crawled data is considered recent enough
+    /// to not be verified again before an auto-battle
+    #[serde(default = "default_verify_window")]
+    pub verify_window: u64,
+    /// The amount of minutes between re-crawls of watched players
+    #[serde(default = "default_watch_interval")]
+    pub watch_interval: u64,
+    /// The amount of hours, that a lured player will not be lured again
+    #[serde(default = "default_lure_cooldown")]
+    pub lure_cooldown: u64,
+    /// Notify, once the souls of a character reach this percentage of the
+    /// limit. 0 disables the notification
+    #[serde(default)]
+    pub souls_alert: u8,
+    #[serde(default)]
+    pub lure_rules: LureRules,
+    #[serde(default)]
+    pub api: ApiConfig,
 
     #[serde(default = "default_locale", skip)]
     pub num_format: CustomFormat,
@@ -46,6 +161,18 @@ fn default_blacklist_threshhold() -> usize {
     2
 }
 
+fn default_verify_window() -> u64 {
+    60
+}
+
+fn default_watch_interval() -> u64 {
+    10
+}
+
+fn default_lure_cooldown() -> u64 {
+    24
+}
+
 fn default_class_icons() -> bool {
     true
 }
@@ -64,6 +191,7 @@ impl Default for Config {
         }
 
         Self {
+            version: CONFIG_VERSION,
             accounts: vec![],
             theme: AvailableTheme::Dark,
             base_name,
@@ -73,13 +201,35 @@ impl Default for Config {
             show_crawling_restrict: false,
             show_class_icons: true,
             blacklist_threshold: default_blacklist_threshhold(),
+            watchlist: vec![],
+            verify_targets: false,
+            verify_window: default_verify_window(),
+            watch_interval: default_watch_interval(),
+            lure_cooldown: default_lure_cooldown(),
+            souls_alert: 0,
+            lure_rules: Default::default(),
+            api: Default::default(),
             num_format: default_locale(),
             start_threads: default_start_threads(),
         }
     }
 }
 
+/// A player on some server, that we want to keep an eye on
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct WatchedPlayer {
+    pub server: String,
+    pub uid: u32,
+    pub name: String,
+}
+
 impl Config {
+    pub fn is_watched(&self, server: ServerID, uid: u32) -> bool {
+        self.watchlist
+            .iter()
+            .any(|a| a.uid == uid && ServerIdent::new(&a.server).id == server)
+    }
+
     pub fn get_sso_accounts_mut(
         &mut self,
         name: &str,
@@ -123,7 +273,8 @@ impl Config {
                     res = Some(config);
                     break;
                 }
-                AccountConfig::SF { characters, .. } => {
+                AccountConfig::SF { characters, .. }
+                | AccountConfig::SSO { characters, .. } => {
                     for c in characters {
                         if ServerIdent::new(&c.ident.server).id != og_server {
                             continue;
@@ -166,7 +317,8 @@ impl Config {
                     res = Some(config);
                     break;
                 }
-                AccountConfig::SF { characters, .. } => {
+                AccountConfig::SF { characters, .. }
+                | AccountConfig::SSO { characters, .. } => {
                     for c in characters {
                         if ServerIdent::new(&c.ident.server).id != og_server {
                             continue;
@@ -184,14 +336,99 @@ impl Config {
         res
     }
 
+    /// Saves the config. Most callers can not do anything about an error, so
+    /// it is also logged here
     pub fn write(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let str = toml::to_string_pretty(self)?;
-        std::fs::write("helper.toml", str)?;
+        let res = self.try_write();
+        if let Err(e) = &res {
+            error!("Could not write the config: {e}");
+        }
+        res
+    }
+
+    fn try_write(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if WRITE_BLOCKED.load(Ordering::Relaxed) {
+            return Err("The config on disk could not be read, so it will \
+                        not be overwritten"
+                .into());
+        }
+        // If the vault is in use, the config only contains references to the
+        // credentials in it
+        let str = match vault::store(&self.accounts)? {
+            Some(accounts) => toml::to_string_pretty(&Config {
+                accounts,
+                ..self.clone()
+            })?,
+            None => toml::to_string_pretty(self)?,
+        };
+        std::fs::write(config_path(), str)?;
         Ok(())
     }
-    pub fn restore() -> Result<Self, Box<dyn std::error::Error>> {
-        let val = std::fs::read_to_string("helper.toml")?;
-        Ok(toml::from_str(&val)?)
+
+    /// Reads the config and migrates it to the current version. The old file
+    /// is backed up before any migration. A missing config is not an error
+    pub fn restore() -> Result<Self, ConfigError> {
+        let val = match std::fs::read_to_string(config_path()) {
+            Ok(val) => val,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let mut raw: toml::Table = toml::from_str(&val)?;
+        let version =
+            raw.get("version").and_then(|a| a.as_integer()).unwrap_or(0);
+        let version = u32::try_from(version).map_err(|_| {
+            ConfigError::Parse(format!("Invalid version {version}"))
+        })?;
+        if version > CONFIG_VERSION {
+            return Err(ConfigError::TooNew(version));
+        }
+
+        let migrate = version < CONFIG_VERSION;
+        if migrate {
+            let backup = config_path_with(&format!(".v{version}.bak"));
+            std::fs::copy(config_path(), &backup)?;
+            info!(
+                "Migrating the config from v{version} to v{CONFIG_VERSION}. \
+                 The old config has been saved as {}",
+                backup.display()
+            );
+            for migration in &MIGRATIONS[version as usize..] {
+                migration(&mut raw);
+            }
+            raw.insert("version".into(), i64::from(CONFIG_VERSION).into());
+        }
+
+        let mut config: Config = toml::Value::Table(raw).try_into()?;
+        vault::resolve(&mut config.accounts);
+        if migrate {
+            _ = config.write();
+        }
+        Ok(config)
+    }
+
+    /// Copies a config, that could not be read, out of the way, so that it is
+    /// not lost, once the default config is written
+    pub fn preserve_broken() -> std::io::Result<String> {
+        let path = config_path_with(&format!(
+            ".{}.broken",
+            Utc::now().format("%Y%m%d-%H%M%S")
+        ));
+        std::fs::copy(config_path(), &path)?;
+        Ok(path.display().to_string())
+    }
+
+    /// Prevents every write of the config, until this is called with false
+    /// again. Used, while the config on disk could not be read, so that it
+    /// is not replaced with the default one
+    pub fn block_writes(blocked: bool) {
+        WRITE_BLOCKED.store(blocked, Ordering::Relaxed);
+    }
+
+    /// Checks if some credentials are still locked in the vault
+    pub fn is_locked(&self) -> bool {
+        vault::has_references(&self.accounts)
     }
 }
 
@@ -209,9 +446,11 @@ pub enum AccountCreds {
     },
 }
 
-impl From<AccountConfig> for AccountCreds {
-    fn from(value: AccountConfig) -> Self {
-        match value {
+impl TryFrom<AccountConfig> for AccountCreds {
+    type Error = ();
+
+    fn try_from(value: AccountConfig) -> Result<Self, ()> {
+        Ok(match value {
             AccountConfig::Regular {
                 name,
                 pw_hash,
@@ -225,7 +464,9 @@ impl From<AccountConfig> for AccountCreds {
             AccountConfig::SF { name, pw_hash, .. } => {
                 AccountCreds::SF { name, pw_hash }
             }
-        }
+            // The login of these is handled by the provider
+            AccountConfig::SSO { .. } => return Err(()),
+        })
     }
 }
 
@@ -245,6 +486,38 @@ pub enum AccountConfig {
         #[serde(default)]
         characters: Vec<SFAccCharacter>,
     },
+    /// A Google, or Steam account. We can not log into these ourselves, so
+    /// the characters log in with their cached sessions
+    SSO {
+        name: String,
+        provider: SSOService,
+        #[serde(default)]
+        characters: Vec<SFAccCharacter>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SSOService {
+    Google,
+    Steam,
+}
+
+impl From<SSOProvider> for SSOService {
+    fn from(value: SSOProvider) -> Self {
+        match value {
+            SSOProvider::Google => SSOService::Google,
+            SSOProvider::Steam => SSOService::Steam,
+        }
+    }
+}
+
+impl std::fmt::Display for SSOService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SSOService::Google => "Google",
+            SSOService::Steam => "Steam",
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -261,6 +534,268 @@ pub struct CharacterConfig {
     pub auto_battle: bool,
     #[serde(default)]
     pub auto_lure: bool,
+    #[serde(default)]
+    pub scoring: TargetScoring,
+    #[serde(default)]
+    pub scrapbook_filter: TargetFilter,
+    #[serde(default)]
+    pub underworld_filter: TargetFilter,
+    #[serde(default)]
+    pub epic_mode: EpicMode,
+    /// The rhai script used for the custom script scoring
+    #[serde(default)]
+    pub score_script: String,
+}
+
+/// Additional restrictions & the sort order for the target lists. The default
+/// value does not filter anything
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct TargetFilter {
+    pub class: Option<Class>,
+    pub min_level: u16,
+    /// Only used for the scrapbook
+    pub min_missing: usize,
+    /// Case insensitive substring, that the guild name has to contain
+    pub guild: String,
+    /// Case insensitive name pattern. `*` matches any amount of characters
+    /// and `?` matches exactly one
+    pub name: String,
+    /// The maximum age of the crawled data in days. 0 means no limit
+    pub max_age: u32,
+    pub sort: Option<TargetSort>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TargetSort {
+    pub column: SortColumn,
+    pub descending: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SortColumn {
+    Missing,
+    Level,
+    Attributes,
+    Items,
+    Name,
+}
+
+impl TargetFilter {
+    /// Checks if the player matches the filter. Values, that are not known
+    /// (yet), like the class of a player restored from a backup, are not
+    /// filtered out, since we can not tell, if they would match
+    pub fn matches(&self, info: &CharacterInfo) -> bool {
+        if let (Some(class), Some(player_class)) = (self.class, info.class) {
+            if player_class != class {
+                return false;
+            }
+        }
+        if info.level < self.min_level {
+            return false;
+        }
+        if !self.guild.trim().is_empty() {
+            let guild = self.guild.trim().to_lowercase();
+            if let Some(player_guild) = &info.guild {
+                if !player_guild.to_lowercase().contains(&guild) {
+                    return false;
+                }
+            }
+        }
+        if !self.name.trim().is_empty()
+            && !glob_match(
+                &self.name.trim().to_lowercase(),
+                &info.name.to_lowercase(),
+            )
+        {
+            return false;
+        }
+        if self.max_age > 0 {
+            if let Some(fetch_date) = info.fetch_date {
+                let age = (Utc::now() - fetch_date).num_days();
+                if age > self.max_age as i64 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns the filter with the sort order changed to `column`. Clicking
+    /// the same column again flips the direction
+    pub fn with_sort(&self, column: SortColumn) -> TargetFilter {
+        let descending = match self.sort {
+            Some(sort) if sort.column == column => !sort.descending,
+            _ => column != SortColumn::Name,
+        };
+        TargetFilter {
+            sort: Some(TargetSort { column, descending }),
+            ..self.clone()
+        }
+    }
+}
+
+/// The rules a crawled player has to match to be considered a lure target
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LureRules {
+    /// The maximum amount of equipped items
+    pub max_items: usize,
+    pub min_level: u16,
+    /// The equipment slots, that have to be empty
+    pub empty_slots: Vec<EquipmentSlot>,
+}
+
+impl Default for LureRules {
+    fn default() -> Self {
+        Self {
+            max_items: 3,
+            min_level: 100,
+            empty_slots: vec![],
+        }
+    }
+}
+
+impl LureRules {
+    pub fn matches(&self, info: &CharacterInfo) -> bool {
+        info.equipment.len() <= self.max_items
+            && info.level >= self.min_level
+            && !info
+                .equipment
+                .iter()
+                .any(|a| self.empty_slots.contains(&a.typ))
+    }
+}
+
+/// The local http api, that other tools can use to check on & control the
+/// helper
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    /// Has to be send as a bearer token with every request
+    pub token: String,
+    /// Expose prometheus metrics on `/metrics`
+    pub metrics: bool,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7878,
+            token: String::new(),
+            metrics: false,
+        }
+    }
+}
+
+impl ApiConfig {
+    pub fn new_token() -> String {
+        let mut rng = fastrand::Rng::new();
+        (0..32).map(|_| rng.alphanumeric()).collect()
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bt)) => {
+                    p = bp + 1;
+                    t = bt + 1;
+                    backtrack = Some((bp, bt + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|a| *a == '*')
+}
+
+/// How the scrapbook targets of a character are ranked
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq,
+)]
+pub enum TargetScoring {
+    /// Every missing item is worth the same
+    #[default]
+    Missing,
+    /// Items, that only few attackable players have equipped, are worth more
+    /// than items, that can be found on lots of players
+    Rarity,
+    /// A user provided script calculates the score
+    Script,
+}
+
+impl TargetScoring {
+    pub const ALL: [TargetScoring; 3] = [
+        TargetScoring::Missing,
+        TargetScoring::Rarity,
+        TargetScoring::Script,
+    ];
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for TargetScoring {
+    fn to_string(&self) -> String {
+        match self {
+            TargetScoring::Missing => "Missing items",
+            TargetScoring::Rarity => "Item rarity",
+            TargetScoring::Script => "Custom script",
+        }
+        .to_string()
+    }
+}
+
+/// Whether, and how epic & legendary items (model ids >= 100) are counted
+/// towards scrapbook targets
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct EpicMode {
+    pub enabled: bool,
+    /// How many normal items a single epic is worth when ranking targets
+    pub weight: usize,
+}
+
+impl Default for EpicMode {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            weight: 1,
+        }
+    }
+}
+
+impl EpicMode {
+    pub fn counts(&self, item: &EquipmentIdent) -> bool {
+        item.model_id < 100 || self.enabled
+    }
+
+    /// The value of the item, if it is missing. Only meaningful for items,
+    /// that are counted at all. The weight can come from the config, or the
+    /// API, so a weight of 0, which would hide epic only targets, is treated
+    /// as 1
+    pub fn weight_of(&self, item: &EquipmentIdent) -> usize {
+        match item.model_id < 100 {
+            true => 1,
+            false => self.weight.max(1),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
@@ -270,6 +805,27 @@ pub struct SFCharIdent {
 }
 
 impl AccountConfig {
+    /// Identifies the account independent of its credentials & settings
+    pub fn key(&self) -> String {
+        match self {
+            AccountConfig::Regular { name, server, .. } => format!(
+                "{}/{}",
+                ServerIdent::new(server).ident,
+                name.to_lowercase()
+            ),
+            AccountConfig::SF { name, .. } => {
+                format!("sf/{}", name.to_lowercase())
+            }
+            AccountConfig::SSO { name, provider, .. } => {
+                format!(
+                    "{}/{}",
+                    provider.to_string().to_lowercase(),
+                    name.to_lowercase()
+                )
+            }
+        }
+    }
+
     pub fn new(creds: AccountCreds) -> AccountConfig {
         match creds {
             AccountCreds::Regular {
@@ -379,3 +935,98 @@ impl AvailableTheme {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// The path of the config can only be set once and writes can be blocked
+    /// globally, so the tests must not run at the same time
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    /// Writes `content` as the config and returns its path
+    fn setup(content: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("sf-helper-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        set_path(dir.join(CONFIG_FILE));
+        Config::block_writes(false);
+
+        let path = config_path().to_path_buf();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn config_with_version(version: Option<i64>) -> String {
+        let raw = toml::to_string(&Config::default()).unwrap();
+        let mut raw: toml::Table = toml::from_str(&raw).unwrap();
+        match version {
+            Some(version) => raw.insert("version".into(), version.into()),
+            None => raw.remove("version"),
+        };
+        toml::to_string(&raw).unwrap()
+    }
+
+    #[test]
+    fn migrates_unversioned_config() {
+        let _lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let old = config_with_version(None);
+        let path = setup(&old);
+        let backup = config_path_with(".v0.bak");
+        _ = std::fs::remove_file(&backup);
+
+        // Both migrations leave the entries as they are, so only the backup
+        // and the new version can be checked
+        let config = Config::restore().unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), old);
+
+        let written: toml::Table =
+            toml::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(
+            written.get("version").and_then(|a| a.as_integer()),
+            Some(i64::from(CONFIG_VERSION))
+        );
+    }
+
+    #[test]
+    fn keeps_newer_config() {
+        let _lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let newer = config_with_version(Some(i64::from(CONFIG_VERSION) + 1));
+        let path = setup(&newer);
+
+        let res = Config::restore();
+        assert!(
+            matches!(res, Err(ConfigError::TooNew(v)) if v == CONFIG_VERSION + 1)
+        );
+
+        Config::block_writes(true);
+        assert!(Config::default().write().is_err());
+        assert_eq!(std::fs::read_to_string(path).unwrap(), newer);
+        Config::block_writes(false);
+    }
+
+    #[test]
+    fn keeps_broken_config() {
+        let _lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let broken = "accounts = [";
+        let path = setup(broken);
+
+        assert!(matches!(Config::restore(), Err(ConfigError::Parse(_))));
+
+        Config::block_writes(true);
+        assert!(Config::default().write().is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), broken);
+
+        let preserved = Config::preserve_broken().unwrap();
+        assert_eq!(std::fs::read_to_string(&preserved).unwrap(), broken);
+        _ = std::fs::remove_file(preserved);
+
+        // Once the broken config is backed up, it may be replaced
+        Config::block_writes(false);
+        assert!(Config::default().write().is_ok());
+        assert!(Config::restore().is_ok());
+    }
+}
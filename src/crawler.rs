@@ -109,7 +109,7 @@ impl Crawler {
                     }
                 }
                 lock.in_flight_pages.retain(|a| a != page);
-                Message::PageCrawled
+                Message::PageCrawled(self.server_id)
             }
             CrawlAction::Character(name, que_id) => {
                 let cmd = Command::ViewPlayer {
@@ -141,35 +141,7 @@ impl Crawler {
                 }
 
                 let character = match gs.lookup.remove_name(name) {
-                    Some(player) => {
-                        let equipment = player
-                            .equipment
-                            .0
-                            .as_array()
-                            .iter()
-                            .flatten()
-                            .filter_map(|a| a.equipment_ident())
-                            .collect();
-                        let stats = player
-                            .base_attributes
-                            .as_array()
-                            .iter()
-                            .sum::<u32>()
-                            + player
-                                .bonus_attributes
-                                .as_array()
-                                .iter()
-                                .sum::<u32>();
-                        CharacterInfo {
-                            equipment,
-                            name: player.name,
-                            uid: player.player_id,
-                            level: player.level,
-                            fetch_date: Some(Utc::now().date_naive()),
-                            stats: Some(stats),
-                            class: Some(player.class),
-                        }
-                    }
+                    Some(player) => CharacterInfo::from_other_player(player),
                     None => {
                         drop(gs);
                         let mut lock = self.que.lock().unwrap();
@@ -178,7 +150,7 @@ impl Crawler {
                             lock.in_flight_accounts.remove(name);
                             lock.invalid_accounts.push(name.to_string());
                         }
-                        return Message::CrawlerNoPlayerResult;
+                        return Message::CrawlerNoPlayerResult(self.server_id);
                     }
                 };
                 Message::CharacterCrawled {
@@ -215,10 +187,23 @@ impl CrawlerState {
     ) -> Result<Self, SFError> {
         let password = name.chars().rev().collect::<String>();
         let mut session = Session::new(&name, &password, server.clone());
+        let server_ident = ServerIdent::new(session.server_url().as_str());
+        if let Some(mut cached) =
+            session_cache::load(&server_ident.ident, &name)
+        {
+            if let Some(gs) = session_cache::resume(&mut cached).await {
+                return Ok(Self {
+                    session: RwLock::new(cached),
+                    gs: Mutex::new(gs),
+                });
+            }
+        }
+
         debug!("Logging in {name} on {}", session.server_url());
         if let Ok(resp) = session.login().await {
             debug!("Successfully logged in {name} on {}", session.server_url());
             let gs = GameState::new(resp)?;
+            session_cache::store(&session);
             sleep(Duration::from_secs(3)).await;
             return Ok(Self {
                 session: RwLock::new(session),
@@ -269,6 +254,7 @@ impl CrawlerState {
         )
         .await?;
         let gs = GameState::new(resp)?;
+        session_cache::store(&session);
 
         debug!("Registered {name} successfull {}", session.server_url());
 
@@ -303,7 +289,15 @@ impl std::fmt::Display for CrawlAction {
 }
 
 #[derive(
-    Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq,
+    Debug,
+    Serialize,
+    Deserialize,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
 )]
 pub enum CrawlingOrder {
     #[default]
@@ -353,6 +347,26 @@ pub struct WorkerQue {
 }
 
 impl WorkerQue {
+    /// Only crawls players between `min` & `max` from now on. Players, that
+    /// were skipped before, but are now in range, are queued again
+    pub fn set_level_range(&mut self, min: u32, max: u32) {
+        self.min_level = min.max(1);
+        self.max_level = max.max(min).min(9999);
+
+        let (min, max) = (self.min_level, self.max_level);
+        let in_range: Vec<u32> = self
+            .lvl_skipped_accounts
+            .keys()
+            .copied()
+            .filter(|lvl| (min..=max).contains(lvl))
+            .collect();
+        for lvl in in_range {
+            if let Some(mut todo) = self.lvl_skipped_accounts.remove(&lvl) {
+                self.todo_accounts.append(&mut todo);
+            }
+        }
+    }
+
     pub fn create_backup(
         &self,
         player_info: &IntMap<u32, CharacterInfo>,
@@ -411,6 +425,15 @@ impl CrawlerError {
         }
         CrawlerError::Generic(value.to_string().into())
     }
+
+    /// A short, stable name of this kind of error
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CrawlerError::Generic(_) => "generic",
+            CrawlerError::NotFound => "not_found",
+            CrawlerError::RateLimit => "rate_limit",
+        }
+    }
 }
 
 async fn sleep_until_rate_limit_reset() {
@@ -0,0 +1,157 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sf_api::gamestate::underworld::{UnderWorldResourceType, Underworld};
+
+use crate::storage;
+
+/// Everything the underworld of a character produced on a single day
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct DailyYield {
+    pub souls: u64,
+    pub pit: u64,
+    pub lure_rewards: u64,
+    pub lures: u32,
+}
+
+/// The state of the underworld resources at a specific point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EconomySample {
+    pub time: DateTime<Local>,
+    pub souls: u64,
+    pub souls_limit: u64,
+    pub pit: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct EconomyData {
+    days: BTreeMap<NaiveDate, DailyYield>,
+    last: Option<EconomySample>,
+}
+
+/// Tracks the souls, gold pit output and lure rewards of a single character
+/// over time. Only positive changes between two samples are counted, so
+/// spending souls or collecting the pit does not show up as a loss
+pub struct UnderworldEconomy {
+    path: PathBuf,
+    pub days: BTreeMap<NaiveDate, DailyYield>,
+    pub last: Option<EconomySample>,
+    /// We already notified the user, that the souls reached the threshold.
+    /// Reset once the souls drop below it again
+    pub notified: bool,
+}
+
+impl UnderworldEconomy {
+    pub fn load(server_ident: &str, name: &str) -> UnderworldEconomy {
+        let path = storage::char_path("economy", server_ident, name, "json");
+        let data: EconomyData = storage::read_json(&path).unwrap_or_default();
+
+        UnderworldEconomy {
+            path,
+            days: data.days,
+            last: data.last,
+            notified: false,
+        }
+    }
+
+    /// Records the current state of the underworld. Returns true, if the
+    /// souls just reached `threshold` percent of the limit. A threshold of 0
+    /// disables the notification
+    pub fn record(&mut self, underworld: &Underworld, threshold: u8) -> bool {
+        let sample = EconomySample {
+            time: Local::now(),
+            souls: underworld.souls_current,
+            souls_limit: underworld.souls_limit,
+            pit: pit_silver(underworld),
+        };
+
+        let changed = self.last.map_or(true, |last| {
+            last.souls != sample.souls
+                || last.souls_limit != sample.souls_limit
+                || last.pit != sample.pit
+        });
+
+        if changed {
+            if let Some(last) = self.last {
+                let today =
+                    self.days.entry(sample.time.date_naive()).or_default();
+                today.souls += sample.souls.saturating_sub(last.souls);
+                today.pit += sample.pit.saturating_sub(last.pit);
+            }
+            self.last = Some(sample);
+            self.write();
+        }
+
+        let full = threshold > 0
+            && sample.souls * 100
+                >= sample.souls_limit * threshold.min(100) as u64;
+        let notify = full && !self.notified;
+        self.notified = full;
+        notify
+    }
+
+    /// Records the silver we got from a single lure
+    pub fn record_lure(&mut self, reward: u64) {
+        let today = self.days.entry(Local::now().date_naive()).or_default();
+        today.lure_rewards += reward;
+        today.lures += 1;
+        self.write();
+    }
+
+    fn write(&self) {
+        let data = EconomyData {
+            days: self.days.clone(),
+            last: self.last,
+        };
+        storage::write_json(&self.path, &data);
+    }
+
+    /// The average yield per day within the last `days` days, not including
+    /// today, because it has not finished yet
+    pub fn avg_per_day(&self, days: i64) -> Option<DailyYield> {
+        let today = Local::now().date_naive();
+        let start = today - Duration::days(days);
+        let mut sum = DailyYield::default();
+        let mut count = 0;
+        for day in self.days.range(start..today).map(|a| a.1) {
+            sum.souls += day.souls;
+            sum.pit += day.pit;
+            sum.lure_rewards += day.lure_rewards;
+            sum.lures += day.lures;
+            count += 1;
+        }
+        if count == 0 {
+            return None;
+        }
+        Some(DailyYield {
+            souls: sum.souls / count,
+            pit: sum.pit / count,
+            lure_rewards: sum.lure_rewards / count,
+            lures: sum.lures / count as u32,
+        })
+    }
+
+    /// The estimated time until the souls are full, based on the average
+    /// amount of souls per day. Zero, if they are already full
+    pub fn until_souls_full(&self) -> Option<Duration> {
+        let last = self.last?;
+        if last.souls >= last.souls_limit {
+            return Some(Duration::zero());
+        }
+        let per_day = self.avg_per_day(7)?.souls;
+        if per_day == 0 {
+            return None;
+        }
+        let remaining = last.souls_limit - last.souls;
+        let minutes = remaining as f64 / per_day as f64 * 24.0 * 60.0;
+        Some(Duration::minutes(minutes.ceil() as i64))
+    }
+}
+
+/// The amount of silver, that can currently be collected from the gold pit
+fn pit_silver(underworld: &Underworld) -> u64 {
+    underworld.production[UnderWorldResourceType::Silver].last_collectable
+}
@@ -0,0 +1,51 @@
+use iced::{
+    futures::{channel::mpsc, executor::block_on, StreamExt},
+    Application, Command, Executor,
+};
+use iced_futures::Runtime;
+use iced_runtime::command::Action;
+use log::{error, info};
+
+use crate::{message::Message, Args, Helper};
+
+type HeadlessRuntime =
+    Runtime<iced::executor::Default, mpsc::UnboundedSender<Message>, Message>;
+
+/// Drives the helper without a window. Commands & subscriptions are run
+/// exactly like the iced shell would, but everything, that would need a
+/// window (clipboard, fonts, etc.) is ignored
+pub fn run(args: Args) -> iced::Result {
+    let executor = iced::executor::Default::new().map_err(|e| {
+        error!("Could not create the executor: {e}");
+        iced::Error::ExecutorCreationFailed(e)
+    })?;
+    let (sender, mut receiver) = mpsc::unbounded();
+    let mut runtime: HeadlessRuntime = Runtime::new(executor, sender);
+
+    let (mut helper, command) = runtime.enter(|| Helper::new(args));
+    run_command(&mut runtime, command);
+    runtime.track(helper.subscription().into_recipes());
+
+    info!("Running headless");
+
+    while let Some(message) = block_on(receiver.next()) {
+        let command = runtime.enter(|| helper.update(message));
+        run_command(&mut runtime, command);
+        runtime.track(helper.subscription().into_recipes());
+    }
+
+    info!("Shutting down");
+    crate::storage::flush();
+    Ok(())
+}
+
+fn run_command(runtime: &mut HeadlessRuntime, command: Command<Message>) {
+    for action in command.actions() {
+        match action {
+            Action::Future(future) => runtime.spawn(future),
+            Action::Stream(stream) => runtime.run(stream),
+            // There is no window, clipboard, or widget tree to act upon
+            _ => {}
+        }
+    }
+}
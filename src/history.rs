@@ -0,0 +1,230 @@
+use std::{fmt::Write, path::PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub time: DateTime<Local>,
+    #[serde(flatten)]
+    pub event: HistoryEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HistoryEvent {
+    Login,
+    Relogin,
+    Fight {
+        target: String,
+        uid: u32,
+        won: bool,
+        items: usize,
+    },
+    Lure {
+        target: String,
+        uid: u32,
+        won: bool,
+    },
+    Mushrooms {
+        amount: u32,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl HistoryEvent {
+    pub fn category(&self) -> HistoryFilter {
+        match self {
+            HistoryEvent::Login | HistoryEvent::Relogin => {
+                HistoryFilter::Logins
+            }
+            HistoryEvent::Fight { .. } => HistoryFilter::Fights,
+            HistoryEvent::Lure { .. } => HistoryFilter::Lures,
+            HistoryEvent::Mushrooms { .. } => HistoryFilter::Mushrooms,
+            HistoryEvent::Error { .. } => HistoryFilter::Errors,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            HistoryEvent::Login => "Logged in".to_string(),
+            HistoryEvent::Relogin => "Logged in again".to_string(),
+            HistoryEvent::Fight {
+                target, won, items, ..
+            } => match won {
+                true => format!("Won against {target} (+{items})"),
+                false => format!("Lost against {target}"),
+            },
+            HistoryEvent::Lure { target, won, .. } => match won {
+                true => format!("Lured {target}"),
+                false => format!("Failed to lure {target}"),
+            },
+            HistoryEvent::Mushrooms { amount } => {
+                format!("Spent {amount} mushroom(s)")
+            }
+            HistoryEvent::Error { message } => format!("Error: {message}"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFilter {
+    #[default]
+    All,
+    Logins,
+    Fights,
+    Lures,
+    Mushrooms,
+    Errors,
+}
+
+impl HistoryFilter {
+    pub const ALL: [HistoryFilter; 6] = [
+        HistoryFilter::All,
+        HistoryFilter::Logins,
+        HistoryFilter::Fights,
+        HistoryFilter::Lures,
+        HistoryFilter::Mushrooms,
+        HistoryFilter::Errors,
+    ];
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for HistoryFilter {
+    fn to_string(&self) -> String {
+        match self {
+            HistoryFilter::All => "All",
+            HistoryFilter::Logins => "Logins",
+            HistoryFilter::Fights => "Fights",
+            HistoryFilter::Lures => "Lures",
+            HistoryFilter::Mushrooms => "Mushrooms",
+            HistoryFilter::Errors => "Errors",
+        }
+        .to_string()
+    }
+}
+
+/// The persistent event log of a single character. Every event gets appended
+/// to a json-lines file in the `history` directory, so that the log survives
+/// restarts
+pub struct AccountHistory {
+    path: PathBuf,
+    pub entries: Vec<HistoryEntry>,
+    pub filter: HistoryFilter,
+    pub search: String,
+    pub export_status: Option<String>,
+}
+
+impl AccountHistory {
+    pub fn load(server_ident: &str, name: &str) -> AccountHistory {
+        let path = storage::char_path("history", server_ident, name, "jsonl");
+        let entries = storage::read_json_lines(&path);
+
+        AccountHistory {
+            path,
+            entries,
+            filter: HistoryFilter::All,
+            search: String::new(),
+            export_status: None,
+        }
+    }
+
+    pub fn push(&mut self, event: HistoryEvent) {
+        let entry = HistoryEntry {
+            time: Local::now(),
+            event,
+        };
+        storage::append_json(&self.path, &entry);
+        self.entries.push(entry);
+    }
+
+    /// All entries, that match the current filter & search, newest first
+    pub fn filtered(&self) -> impl Iterator<Item = &HistoryEntry> {
+        let search = self.search.trim().to_lowercase();
+        self.entries.iter().rev().filter(move |a| {
+            if self.filter != HistoryFilter::All
+                && a.event.category() != self.filter
+            {
+                return false;
+            }
+            search.is_empty()
+                || a.event.describe().to_lowercase().contains(&search)
+        })
+    }
+
+    pub fn csv_path(&self) -> PathBuf {
+        self.path.with_extension("csv")
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut res =
+            String::from("time,kind,target,uid,won,items,amount,message\n");
+        for entry in self.filtered() {
+            let (target, uid, won, items, amount, message) = match &entry.event
+            {
+                HistoryEvent::Login | HistoryEvent::Relogin => {
+                    (None, None, None, None, None, None)
+                }
+                HistoryEvent::Fight {
+                    target,
+                    uid,
+                    won,
+                    items,
+                } => (
+                    Some(target.as_str()),
+                    Some(*uid),
+                    Some(*won),
+                    Some(*items),
+                    None,
+                    None,
+                ),
+                HistoryEvent::Lure { target, uid, won } => (
+                    Some(target.as_str()),
+                    Some(*uid),
+                    Some(*won),
+                    None,
+                    None,
+                    None,
+                ),
+                HistoryEvent::Mushrooms { amount } => {
+                    (None, None, None, None, Some(*amount), None)
+                }
+                HistoryEvent::Error { message } => {
+                    (None, None, None, None, None, Some(message.as_str()))
+                }
+            };
+            let kind = match &entry.event {
+                HistoryEvent::Login => "login",
+                HistoryEvent::Relogin => "relogin",
+                HistoryEvent::Fight { .. } => "fight",
+                HistoryEvent::Lure { .. } => "lure",
+                HistoryEvent::Mushrooms { .. } => "mushrooms",
+                HistoryEvent::Error { .. } => "error",
+            };
+            _ = writeln!(
+                res,
+                "{},{kind},{},{},{},{},{},{}",
+                entry.time.to_rfc3339(),
+                csv_field(target.unwrap_or_default()),
+                uid.map(|a| a.to_string()).unwrap_or_default(),
+                won.map(|a| a.to_string()).unwrap_or_default(),
+                items.map(|a| a.to_string()).unwrap_or_default(),
+                amount.map(|a| a.to_string()).unwrap_or_default(),
+                csv_field(message.unwrap_or_default()),
+            );
+        }
+        res
+    }
+}
+
+fn csv_field(val: &str) -> String {
+    if val.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", val.replace('"', "\"\""))
+    } else {
+        val.to_string()
+    }
+}
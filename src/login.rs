@@ -20,9 +20,11 @@ use sf_api::{
 use tokio::time::sleep;
 
 use crate::{
-    config::AccountConfig, get_server_code, message::Message, top_bar,
-    AccountID, AccountIdent, AccountInfo, AccountPage, Helper, ServerIdent,
-    View,
+    config::{AccountConfig, SFAccCharacter, SSOService},
+    get_server_code,
+    message::Message,
+    session_cache, top_bar, vault, AccountID, AccountIdent, AccountInfo,
+    AccountPage, Helper, ServerIdent, View,
 };
 
 pub struct LoginState {
@@ -32,21 +34,49 @@ pub struct LoginState {
     pub server: String,
     pub remember_me: bool,
     pub error: Option<String>,
+    /// The master password of the credential vault, while it is entered
+    pub vault_password: String,
+    /// Why the vault could not be enabled, or disabled in the settings
+    pub vault_error: Option<String>,
+    /// Set, if a Google, or Steam account has been saved, but the sessions
+    /// of its characters could not be kept for the next start
+    pub sso_notice: Option<String>,
     pub active_sso: Vec<SSOLogin>,
     pub import_que: Vec<Session>,
     pub google_sso: Arc<Mutex<SSOStatus>>,
     pub steam_sso: Arc<Mutex<SSOStatus>>,
 }
 
+/// The state of the browser login of a SSO provider
 pub enum SSOStatus {
-    Waiting { url: String },
     Initializing,
+    /// Waiting for the user to log in through the browser
+    Waiting {
+        url: String,
+    },
+    /// The user did not log in in time
+    TimedOut,
+    Failed(String),
+    Cancelled,
+    /// The account with this name has been logged in
+    Success(String),
+}
+
+impl SSOStatus {
+    /// Checks if the login is still in progress
+    pub fn is_active(&self) -> bool {
+        matches!(self, SSOStatus::Initializing | SSOStatus::Waiting { .. })
+    }
 }
 
 #[derive(Debug)]
 pub struct SSOLogin {
     pub ident: SSOIdent,
     pub status: SSOLoginStatus,
+    /// The password of a S&F account, so that the login can be retried
+    pub pw_hash: Option<PWHash>,
+    /// Whether the account should be saved, once the login succeeded
+    pub remember: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -60,9 +90,17 @@ pub enum SSOIdent {
 pub enum SSOLoginStatus {
     Loading,
     Success,
+    Error(String),
 }
 
 impl LoginState {
+    pub fn sso_status(&self, provider: SSOProvider) -> &Arc<Mutex<SSOStatus>> {
+        match provider {
+            SSOProvider::Google => &self.google_sso,
+            SSOProvider::Steam => &self.steam_sso,
+        }
+    }
+
     pub fn view(
         &self,
         accounts: &[AccountConfig],
@@ -194,14 +232,10 @@ impl LoginState {
                 let title: widget::text::Text<'_, Theme, Renderer> =
                     text("Steam").size(20);
 
-                let info: Element<Message> =
-                    match &*self.steam_sso.lock().unwrap() {
-                        SSOStatus::Waiting { url } => button(text("Login"))
-                            .on_press(Message::OpenLink(url.to_string()))
-                            .into(),
-                        _ => text("Waiting...").into(),
-                    };
-
+                let info = view_sso_status(
+                    SSOProvider::Steam,
+                    &self.steam_sso.lock().unwrap(),
+                );
                 let info = container(info).padding(20);
                 column!(title, info)
             }
@@ -209,14 +243,10 @@ impl LoginState {
                 let title: widget::text::Text<'_, Theme, Renderer> =
                     text("Google").size(20);
 
-                let info: Element<Message> =
-                    match &*self.google_sso.lock().unwrap() {
-                        SSOStatus::Waiting { url } => button(text("Login"))
-                            .on_press(Message::OpenLink(url.to_string()))
-                            .into(),
-                        _ => text("Waiting...").into(),
-                    };
-
+                let info = view_sso_status(
+                    SSOProvider::Google,
+                    &self.google_sso.lock().unwrap(),
+                );
                 let info = container(info).padding(20);
                 column!(title, info)
             }
@@ -274,6 +304,34 @@ impl LoginState {
                             .width(Length::Fill);
                             accounts_col = accounts_col.push(button);
                         }
+                        AccountConfig::SSO { name, provider, .. } => {
+                            let login_msg = Message::Login {
+                                account: acc.clone(),
+                                auto_login: false,
+                            };
+
+                            let button = button(
+                                row!(
+                                    text(
+                                        titlecase::titlecase(name).to_string()
+                                    ),
+                                    horizontal_space(),
+                                    text(provider.to_string())
+                                )
+                                .width(Length::Fill),
+                            )
+                            .on_press(login_msg)
+                            .style(theme::Button::Positive)
+                            .width(Length::Fill);
+                            let forget = button("Forget")
+                                .on_press(Message::SSOForget {
+                                    name: name.clone(),
+                                    provider: *provider,
+                                })
+                                .style(theme::Button::Destructive);
+                            accounts_col = accounts_col
+                                .push(row!(button, forget).spacing(10));
+                        }
                     };
                 }
 
@@ -292,6 +350,10 @@ impl LoginState {
                     .align_items(Alignment::Center);
 
                 for active in &self.active_sso {
+                    if let SSOLoginStatus::Error(error) = &active.status {
+                        col = col.push(view_sso_error(&active.ident, error));
+                        continue;
+                    }
                     let button = button(
                         row!(
                             text(
@@ -305,7 +367,7 @@ impl LoginState {
                             horizontal_space(),
                             text(match active.status {
                                 SSOLoginStatus::Loading => "Loading..",
-                                SSOLoginStatus::Success => "",
+                                _ => "",
                             })
                         )
                         .width(Length::Fill),
@@ -313,11 +375,11 @@ impl LoginState {
                     .width(Length::Fill)
                     .style(match active.status {
                         SSOLoginStatus::Loading => theme::Button::Secondary,
-                        SSOLoginStatus::Success => theme::Button::Positive,
+                        _ => theme::Button::Positive,
                     })
                     .on_press_maybe(match active.status {
                         SSOLoginStatus::Loading => None,
-                        SSOLoginStatus::Success => {
+                        _ => {
                             Some(Message::LoginViewChanged(LoginType::SSOChars))
                         }
                     });
@@ -355,7 +417,11 @@ impl LoginState {
 
                     col = col.push(button);
                 }
-                column!(title, widget::scrollable(col))
+                let mut res = column!(title);
+                if let Some(notice) = &self.sso_notice {
+                    res = res.push(text(notice).size(12));
+                }
+                res.push(widget::scrollable(col))
             }
         };
 
@@ -393,7 +459,37 @@ pub struct SSOValidator {
 }
 
 impl SSOValidator {
-    pub async fn check(
+    pub async fn check(&self) -> Message {
+        // The subscription is dropped, once the login is no longer active,
+        // but we might be polled once more, before that happens
+        if !self.status.lock().unwrap().is_active() {
+            sleep(Duration::from_secs(1)).await;
+            return Message::UIActive;
+        }
+        let provider = self.provider;
+        let (status, msg) = match self.poll().await {
+            Ok(Some((chars, name))) => (
+                SSOStatus::Success(name.clone()),
+                Message::SSOSuccess {
+                    auth_name: name,
+                    chars: chars.into_iter().flatten().collect(),
+                    provider,
+                },
+            ),
+            Ok(None) => (SSOStatus::TimedOut, Message::SSOTimedOut(provider)),
+            Err(e) => (
+                SSOStatus::Failed(e.to_string()),
+                Message::SSOAuthError {
+                    provider,
+                    error: e.to_string(),
+                },
+            ),
+        };
+        *self.status.lock().unwrap() = status;
+        msg
+    }
+
+    async fn poll(
         &self,
     ) -> Result<Option<(Vec<Result<Session, SFError>>, String)>, SFError> {
         sleep(Duration::from_millis(fastrand::u64(500..=1000))).await;
@@ -408,7 +504,6 @@ impl SSOValidator {
             let resp = auth.try_login().await?;
             match resp {
                 sf_api::sso::AuthResponse::Success(res) => {
-                    println!("Success");
                     let name = res.username().to_string();
                     let chars = res.characters().await?;
                     return Ok(Some((chars, name)));
@@ -419,14 +514,76 @@ impl SSOValidator {
             }
             sleep(Duration::from_secs(6)).await;
         }
-        {
-            *self.status.lock().unwrap() = SSOStatus::Initializing
-        }
         Ok(None)
     }
 }
 
+fn view_sso_status(
+    provider: SSOProvider,
+    status: &SSOStatus,
+) -> Element<'static, Message> {
+    let cancel = button("Cancel")
+        .on_press(Message::SSOCancel(provider))
+        .style(theme::Button::Destructive);
+    let retry = |label| button(label).on_press(Message::SSOStart(provider));
+
+    let res = match status {
+        SSOStatus::Initializing => {
+            column!(text("Preparing the login..."), cancel)
+        }
+        SSOStatus::Waiting { url } => column!(
+            text("Waiting for the login in your browser"),
+            row!(
+                button(text("Login"))
+                    .on_press(Message::OpenLink(url.to_string())),
+                cancel
+            )
+            .spacing(10)
+        ),
+        SSOStatus::TimedOut => {
+            column!(text("The login timed out"), retry("Retry"))
+        }
+        SSOStatus::Failed(error) => {
+            column!(text(format!("Error: {error}")), retry("Retry"))
+        }
+        SSOStatus::Cancelled => {
+            column!(text("The login has been cancelled"), retry("Start"))
+        }
+        SSOStatus::Success(name) => column!(
+            text(format!("Logged in as {name}")),
+            retry("Add another account")
+        ),
+    };
+    res.spacing(10).align_items(Alignment::Center).into()
+}
+
+fn view_sso_error<'a>(ident: &SSOIdent, error: &str) -> Element<'a, Message> {
+    let name = match ident {
+        SSOIdent::SF(name) | SSOIdent::Google(name) | SSOIdent::Steam(name) => {
+            name.as_str()
+        }
+    };
+    row!(
+        column!(
+            text(titlecase::titlecase(name).to_string()),
+            text(format!("Error: {error}")).size(12)
+        ),
+        horizontal_space(),
+        button("Retry").on_press(Message::SSORetryLogin(ident.clone())),
+        button("Dismiss")
+            .on_press(Message::SSODismiss(ident.clone()))
+            .style(theme::Button::Destructive),
+    )
+    .spacing(10)
+    .width(Length::Fill)
+    .align_items(Alignment::Center)
+    .into()
+}
+
 impl Helper {
+    /// Logs in a character with its name & password. `saved` has to be set,
+    /// if the credentials come from the config. Only then a cached session
+    /// may be used, because that skips checking the password
     pub fn login_regular(
         &mut self,
         name: String,
@@ -434,6 +591,7 @@ impl Helper {
         pw_hash: PWHash,
         remember: bool,
         auto_login: bool,
+        saved: bool,
     ) -> Command<Message> {
         let name = name.trim().to_string();
         let server = server.trim().to_string();
@@ -446,34 +604,101 @@ impl Helper {
         let session =
             sf_api::session::Session::new_hashed(&name, pw_hash.clone(), con);
 
-        self.login(session, remember, PlayerAuth::Normal(pw_hash), auto_login)
+        self.login(
+            session,
+            remember,
+            PlayerAuth::Normal(pw_hash),
+            auto_login,
+            saved,
+        )
     }
 
+    /// Logs in the session. A cached session of the character is only
+    /// resumed, if `use_cache` is set
     pub fn login(
         &mut self,
         mut session: sf_api::session::Session,
         remember: bool,
         auth: PlayerAuth,
         auto_login: bool,
+        use_cache: bool,
     ) -> Command<Message> {
-        let server_ident = ServerIdent::new(session.server_url().as_str());
+        let Some((account_ident, cache_ident, name)) = self.insert_account(
+            session.server_url().as_str(),
+            session.username(),
+            auth,
+            auto_login,
+        ) else {
+            return Command::none();
+        };
+        static WAITING: AtomicU64 = AtomicU64::new(0);
+
+        Command::perform(
+            async move {
+                // A cached session does not need a full login, so we also
+                // do not have to wait for other logins to finish
+                if let Some(mut cached) = use_cache
+                    .then(|| session_cache::load(&cache_ident, &name))
+                    .flatten()
+                {
+                    if let Some(gs) = session_cache::resume(&mut cached).await {
+                        return Ok((Box::new(gs), Box::new(cached)));
+                    }
+                }
+                // This likely has some logic issues
+                let w =
+                    WAITING.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                if w > 0 {
+                    sleep(Duration::from_secs(w)).await;
+                }
+                let resp = session.login().await.inspect(|_| {
+                    WAITING.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                })?;
+                let gs = GameState::new(resp)?;
+                let gs = Box::new(gs);
+                Ok((gs, Box::new(session)))
+            },
+            move |a: Result<_, SFError>| match a {
+                Ok((gs, session)) => Message::LoggininSuccess {
+                    ident: account_ident,
+                    gs,
+                    session,
+                    remember,
+                },
+                Err(err) => Message::LoggininFailure {
+                    ident: account_ident,
+                    error: err.to_string(),
+                },
+            },
+        )
+    }
+
+    /// Adds the account of a character to its server, so that it shows up,
+    /// while it is logging in. Returns `None`, if it is already logged in,
+    /// or the server is not valid
+    fn insert_account(
+        &mut self,
+        server_url: &str,
+        name: &str,
+        auth: PlayerAuth,
+        auto_login: bool,
+    ) -> Option<(AccountIdent, String, String)> {
+        let server_ident = ServerIdent::new(server_url);
         let Some(connection) = ServerConnection::new(&server_ident.url) else {
             self.login_state.error =
                 Some("Server Url is not valid".to_string());
-            return Command::none();
+            return None;
         };
-        let name: String = session
-            .username()
-            .chars()
-            .map(|a| a.to_ascii_lowercase())
-            .collect();
+        let name: String =
+            name.chars().map(|a| a.to_ascii_lowercase()).collect();
 
         let account_id = AccountID::new();
         let account_ident = AccountIdent {
             server_id: server_ident.id,
             account: account_id,
         };
-        let info = AccountInfo::new(&name, auth, account_ident);
+        let info =
+            AccountInfo::new(&name, auth, account_ident, &server_ident.ident);
         let server = self
             .servers
             .get_or_insert_default(server_ident, connection, None);
@@ -485,7 +710,7 @@ impl Helper {
                 ident: existing.ident,
                 page: AccountPage::Scrapbook,
             };
-            return Command::none();
+            return None;
         }
         if !auto_login {
             self.current_view = View::Account {
@@ -493,39 +718,91 @@ impl Helper {
                 page: AccountPage::Scrapbook,
             };
         }
+        let cache_ident = server.ident.ident.clone();
         server.accounts.insert(info.ident.account, info);
-        static WAITING: AtomicU64 = AtomicU64::new(0);
+        Some((account_ident, cache_ident, name))
+    }
+
+    /// Logs in a character of a Google, or Steam account with its cached
+    /// session. These can not do a full login on their own, so an expired
+    /// session has to be renewed through the browser login
+    fn resume_sso(
+        &mut self,
+        provider: SSOService,
+        server_url: &str,
+        name: &str,
+        auto_login: bool,
+    ) -> Command<Message> {
+        let Some((account_ident, cache_ident, name)) =
+            self.insert_account(server_url, name, PlayerAuth::SSO, auto_login)
+        else {
+            return Command::none();
+        };
 
         Command::perform(
             async move {
-                // This likely has some logic issues
-                let w =
-                    WAITING.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-                if w > 0 {
-                    sleep(Duration::from_secs(w)).await;
+                let Some(mut session) =
+                    session_cache::load(&cache_ident, &name)
+                else {
+                    let reason = match vault::is_unlocked() {
+                        true => "No saved session",
+                        false => "Sessions are only kept with the vault",
+                    };
+                    return Err(format!(
+                        "{reason}. Please log in with {provider} again"
+                    ));
+                };
+                match session_cache::resume(&mut session).await {
+                    Some(gs) => Ok((Box::new(gs), Box::new(session))),
+                    None => {
+                        // The session is useless now, so we do not try it
+                        // again on the next start
+                        session_cache::remove(&cache_ident, &name);
+                        Err(format!(
+                            "The saved session expired. Please log in with \
+                             {provider} again"
+                        ))
+                    }
                 }
-                let resp = session.login().await.inspect(|_| {
-                    WAITING.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
-                })?;
-                let gs = GameState::new(resp)?;
-                let gs = Box::new(gs);
-                Ok((gs, Box::new(session)))
             },
-            move |a: Result<_, SFError>| match a {
+            move |res| match res {
                 Ok((gs, session)) => Message::LoggininSuccess {
                     ident: account_ident,
                     gs,
                     session,
-                    remember,
+                    remember: false,
                 },
-                Err(err) => Message::LoggininFailure {
+                Err(error) => Message::LoggininFailure {
                     ident: account_ident,
-                    error: err.to_string(),
+                    error,
                 },
             },
         )
     }
 
+    /// Logs in the characters of a Google, or Steam account with their
+    /// cached sessions. Characters without a valid one have to go through the
+    /// browser login again
+    pub fn login_sso_acc(
+        &mut self,
+        provider: SSOService,
+        characters: Vec<SFAccCharacter>,
+        auto_login: bool,
+    ) -> Command<Message> {
+        let mut commands = vec![];
+        for SFAccCharacter { ident, config } in characters {
+            if auto_login && !config.login {
+                continue;
+            }
+            commands.push(
+                self.resume_sso(
+                    provider, &ident.server, &ident.name, auto_login,
+                ),
+            );
+        }
+        Command::batch(commands)
+    }
+
     pub fn login_sf_acc(
         &mut self,
         name: String,
@@ -535,17 +812,19 @@ impl Helper {
     ) -> Command<Message> {
         let ident = SSOIdent::SF(name.clone());
         self.login_state.login_typ = LoginType::SSOAccounts;
-        if self
-            .login_state
-            .active_sso
-            .iter()
-            .any(|a| matches!(&a.ident, SSOIdent::SF(s) if s.as_str() == name.as_str()))
-        {
+        // A failed login is replaced by this one. Everything else is either
+        // still loading, or already logged in
+        self.login_state.active_sso.retain(|a| {
+            a.ident != ident || !matches!(a.status, SSOLoginStatus::Error(_))
+        });
+        if self.login_state.active_sso.iter().any(|a| a.ident == ident) {
             return Command::none();
         }
         self.login_state.active_sso.push(SSOLogin {
             ident: ident.clone(),
             status: SSOLoginStatus::Loading,
+            pw_hash: Some(pwhash.clone()),
+            remember: remember_sf,
         });
 
         let n2 = name.clone();
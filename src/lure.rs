@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Local};
+use nohash_hasher::IntMap;
+use sf_api::gamestate::{character::Class, underworld::Underworld};
+
+use crate::{
+    history::{HistoryEntry, HistoryEvent},
+    CharacterInfo,
+};
+
+/// How much weight the level based estimate has compared to actual outcomes.
+/// A prior of 2.0 means, that the estimate counts as two past lures
+const PRIOR_WEIGHT: f32 = 2.0;
+
+/// The past lures against a single player
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LureRecord {
+    pub won: u32,
+    pub lost: u32,
+    /// The amount of lures, that failed since the last successful one
+    pub failed_in_row: u32,
+    pub last: Option<DateTime<Local>>,
+}
+
+/// Everything we learned from past lures of a single character. This is
+/// built from the history once and then kept up to date with every new lure,
+/// so it does not need to be persisted on its own
+#[derive(Debug, Default)]
+pub struct LureStats {
+    targets: IntMap<u32, LureRecord>,
+    classes: HashMap<Class, (u32, u32)>,
+    won: u32,
+    lost: u32,
+}
+
+impl LureStats {
+    pub fn new(
+        history: &[HistoryEntry],
+        player_info: &IntMap<u32, CharacterInfo>,
+    ) -> Self {
+        let mut stats = LureStats::default();
+        for entry in history {
+            let HistoryEvent::Lure { uid, won, .. } = &entry.event else {
+                continue;
+            };
+            let class = player_info.get(uid).and_then(|a| a.class);
+            stats.record(*uid, class, *won, entry.time);
+        }
+        stats
+    }
+
+    pub fn record(
+        &mut self,
+        uid: u32,
+        class: Option<Class>,
+        won: bool,
+        time: DateTime<Local>,
+    ) {
+        let record = self.targets.entry(uid).or_default();
+        record.last = Some(time);
+        let class = class.map(|a| self.classes.entry(a).or_default());
+        if won {
+            record.won += 1;
+            record.failed_in_row = 0;
+            self.won += 1;
+            if let Some(class) = class {
+                class.0 += 1;
+            }
+        } else {
+            record.lost += 1;
+            record.failed_in_row += 1;
+            self.lost += 1;
+            if let Some(class) = class {
+                class.1 += 1;
+            }
+        }
+    }
+
+    pub fn get(&self, uid: u32) -> Option<&LureRecord> {
+        self.targets.get(&uid)
+    }
+
+    /// Failed `threshold` times in a row against this player
+    pub fn is_blacklisted(&self, uid: u32, threshold: usize) -> bool {
+        self.get(uid)
+            .is_some_and(|a| a.failed_in_row as usize >= threshold.max(1))
+    }
+
+    /// We lured this player in the last `hours` hours
+    pub fn on_cooldown(&self, uid: u32, hours: u64) -> bool {
+        let Some(last) = self.get(uid).and_then(|a| a.last) else {
+            return false;
+        };
+        last + Duration::hours(hours as i64) > Local::now()
+    }
+
+    /// Estimates the chance to successfully lure the player. The estimate is
+    /// based on the level difference between our units and the target,
+    /// adjusted by how well we did against this class and this player in the
+    /// past
+    pub fn chance(&self, info: &CharacterInfo, unit_level: f32) -> f32 {
+        let diff = unit_level - info.level as f32;
+        let mut chance = 1.0 / (1.0 + (-diff / 10.0).exp());
+
+        if let Some(class) = info.class.and_then(|a| self.classes.get(&a)) {
+            let overall = (self.won as f32 + 1.0)
+                / (self.won as f32 + self.lost as f32 + 2.0);
+            let class_rate = (class.0 as f32 + overall * PRIOR_WEIGHT)
+                / (class.0 as f32 + class.1 as f32 + PRIOR_WEIGHT);
+            chance *= (class_rate / overall).clamp(0.5, 1.5);
+        }
+
+        if let Some(record) = self.get(info.uid) {
+            chance = (record.won as f32 + chance * PRIOR_WEIGHT)
+                / (record.won as f32 + record.lost as f32 + PRIOR_WEIGHT);
+        }
+        chance.clamp(0.01, 0.99)
+    }
+}
+
+/// The average level of the units, that defend the underworld
+pub fn avg_unit_level(underworld: &Underworld) -> f32 {
+    underworld
+        .units
+        .as_array()
+        .iter()
+        .map(|a| a.level as u64)
+        .sum::<u64>() as f32
+        / 3.0
+}
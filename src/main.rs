@@ -1,22 +1,44 @@
 #![windows_subsystem = "windows"]
+mod api;
 mod backup;
+mod cli_crawl;
 mod config;
 mod crawler;
+mod economy;
+mod headless;
+mod history;
 mod login;
+mod lure;
 mod message;
+mod metrics;
 mod player;
+mod profile;
+mod progress;
+mod query;
+mod scripting;
 mod server;
+mod session_cache;
+mod storage;
+mod transfer;
 mod ui;
+mod vault;
+mod watchlist;
 
 use std::{
     collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
+    path::PathBuf,
     sync::{atomic::AtomicU64, Arc, Mutex},
     time::Duration,
 };
 
-use chrono::{Local, NaiveDate, Utc};
-use clap::{Parser, Subcommand};
-use config::{AccountConfig, Config};
+use backup::RestoreData;
+use chrono::{DateTime, Local, Utc};
+use clap::{CommandFactory, Parser, Subcommand};
+use cli_crawl::{read_server_list, CrawlEvent, CrawlOptions};
+use config::{
+    AccountConfig, CharacterConfig, Config, ConfigError, EpicMode, LureRules,
+    SFAccCharacter, SFCharIdent, SSOService, TargetScoring,
+};
 use crawler::{CrawlAction, Crawler, CrawlerState, CrawlingOrder, WorkerQue};
 use iced::{
     executor, subscription, theme,
@@ -25,29 +47,38 @@ use iced::{
     Theme,
 };
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use log::{debug, info, trace};
+use log::{debug, error, info, trace};
 use log4rs::{
     append::{
         console::{ConsoleAppender, Target},
         file::FileAppender,
     },
     config::{Appender, Logger, Root},
-    encode::pattern::PatternEncoder,
+    encode::{json::JsonEncoder, pattern::PatternEncoder},
+    filter::threshold::ThresholdFilter,
 };
 use login::{LoginState, LoginType, PlayerAuth, SSOStatus, SSOValidator};
+use lure::{avg_unit_level, LureStats};
+use metrics::Metrics;
 use nohash_hasher::{IntMap, IntSet};
 use player::{
     AccountInfo, AccountStatus, AutoAttackChecker, AutoLureChecker, AutoPoll,
     ScrapbookInfo,
 };
+use query::QueryArgs;
+use scripting::Scorer;
 use serde::{Deserialize, Serialize};
 use server::{CrawlingStatus, ServerIdent, ServerInfo, Servers};
 use sf_api::{
-    gamestate::{character::Class, unlockables::EquipmentIdent},
+    gamestate::{
+        character::Class, social::OtherPlayer, unlockables::EquipmentIdent,
+    },
     session::ServerConnection,
     sso::{SSOProvider, ServerLookup},
 };
 use tokio::time::sleep;
+use transfer::AccountsCommand;
+use watchlist::WatchAlert;
 
 use crate::{
     config::{AccountCreds, AvailableTheme},
@@ -60,6 +91,13 @@ pub const PER_PAGE: usize = 51;
 struct Args {
     #[command(subcommand)]
     pub sub: Option<CLICommand>,
+    /// Use a separate profile with its own config, backups & history. The
+    /// files are stored in `profiles/<name>`
+    #[arg(short, long, global = true)]
+    pub profile: Option<String>,
+    /// Read and write the config from this file instead of `helper.toml`
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
 }
 
 #[derive(Debug, Subcommand, Clone)]
@@ -73,7 +111,19 @@ enum CLICommand {
         threads: usize,
         #[clap(flatten)]
         servers: ServerSelect,
+        #[clap(flatten)]
+        options: CrawlOptions,
     },
+    /// Logs in every saved account, that has `login` enabled and runs their
+    /// automations & the crawler without opening a window. Logs are written
+    /// to stderr as json lines
+    Run,
+    /// Prints the best targets, or battle order of a character based on the
+    /// local hall of fame backup of a server. Does not log in
+    Query(QueryArgs),
+    /// Exports, or imports saved accounts
+    #[command(subcommand)]
+    Accounts(AccountsCommand),
 }
 fn concurrency_limits(s: &str) -> Result<usize, String> {
     clap_num::number_range(s, 1, 50)
@@ -88,6 +138,9 @@ pub struct ServerSelect {
     /// The list of all server urls to fetch
     #[arg(short, long, value_delimiter = ' ', num_args = 1..)]
     urls: Option<Vec<String>>,
+    /// A file with one server url per line
+    #[arg(short, long)]
+    file: Option<PathBuf>,
 }
 
 impl Args {
@@ -97,13 +150,41 @@ impl Args {
 }
 
 fn main() -> iced::Result {
-    let args = Args::parse();
+    let mut args = Args::parse();
+    if let Err(e) = profile::init(args.profile.clone()) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+    if let Some(path) = args.config.take() {
+        config::set_path(profile::absolute(path));
+    }
+    if let Some(CLICommand::Crawl {
+        servers, options, ..
+    }) = &mut args.sub
+    {
+        if let Err(e) = options.validate() {
+            Args::command()
+                .error(clap::error::ErrorKind::ArgumentConflict, e)
+                .exit();
+        }
+        servers.file = servers.file.take().map(profile::absolute);
+    }
 
     let is_headless = args.is_headless();
-    let config = get_log_config(is_headless);
+    let is_daemon = matches!(args.sub, Some(CLICommand::Run));
+    let config = get_log_config(is_headless, is_daemon);
     log4rs::init_config(config).unwrap();
     info!("Starting up");
 
+    if is_daemon {
+        return headless::run(args);
+    }
+    match args.sub {
+        Some(CLICommand::Query(query)) => return query::run(query),
+        Some(CLICommand::Accounts(cmd)) => return transfer::run(cmd),
+        _ => {}
+    }
+
     let mut settings = Settings::with_flags(args);
     settings.window.min_size = Some(iced::Size {
         width: 700.0,
@@ -123,7 +204,9 @@ fn main() -> iced::Result {
             iced::window::icon::from_rgba(img.into_bytes(), width, height).ok();
         settings.window.icon = icon;
     }
-    Helper::run(settings)
+    let res = Helper::run(settings);
+    storage::flush();
+    res
 }
 
 struct Helper {
@@ -134,6 +217,22 @@ struct Helper {
     should_update: bool,
     class_images: ClassImages,
     cli_crawling: Option<CLICrawling>,
+    alerts: Vec<WatchAlert>,
+    /// The names of all characters, whose souls reached the configured
+    /// threshold
+    souls_alerts: Vec<String>,
+    /// Why the config could not be read, if it could not be read
+    config_error: Option<ConfigIssue>,
+    /// The name of the profile, that is about to be created
+    new_profile: String,
+    metrics: Metrics,
+}
+
+struct ConfigIssue {
+    message: String,
+    /// Whether the default config may replace the one on disk, once the
+    /// user dismissed the error. Only the case, if there is a backup of it
+    recoverable: bool,
 }
 
 struct CLICrawling {
@@ -141,6 +240,7 @@ struct CLICrawling {
     mbp: MultiProgress,
     threads: usize,
     active: usize,
+    options: CrawlOptions,
 }
 
 struct ClassImages {
@@ -225,6 +325,9 @@ enum View {
     },
     Login,
     Settings,
+    Watchlist,
+    /// Asks for the master password of the credential vault
+    Unlock,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -237,6 +340,8 @@ pub enum ActionSelection {
 enum AccountPage {
     Scrapbook,
     Underworld,
+    Items,
+    History,
     Options,
 }
 
@@ -266,14 +371,77 @@ pub struct CharacterInfo {
     #[serde(skip)]
     stats: Option<u32>,
     #[serde(skip)]
-    fetch_date: Option<NaiveDate>,
+    fetch_date: Option<DateTime<Utc>>,
     #[serde(skip)]
     class: Option<Class>,
+    #[serde(skip)]
+    guild: Option<String>,
 }
 
 impl CharacterInfo {
+    pub fn from_other_player(player: OtherPlayer) -> CharacterInfo {
+        let equipment = player
+            .equipment
+            .0
+            .as_array()
+            .iter()
+            .flatten()
+            .filter_map(|a| a.equipment_ident())
+            .collect();
+        let stats = player.base_attributes.as_array().iter().sum::<u32>()
+            + player.bonus_attributes.as_array().iter().sum::<u32>();
+        CharacterInfo {
+            equipment,
+            name: player.name,
+            uid: player.player_id,
+            level: player.level,
+            fetch_date: Some(Utc::now()),
+            stats: Some(stats),
+            class: Some(player.class),
+            guild: player.guild,
+        }
+    }
+
     pub fn is_old(&self) -> bool {
-        self.fetch_date.unwrap_or_default() < Utc::now().date_naive()
+        self.fetch_date.unwrap_or_default().date_naive()
+            < Utc::now().date_naive()
+    }
+
+    /// Checks if the info has been fetched within the last `minutes` minutes
+    pub fn is_fresh(&self, minutes: u64) -> bool {
+        self.fetch_date.is_some_and(|a| {
+            Utc::now() - a <= chrono::Duration::minutes(minutes as i64)
+        })
+    }
+
+    /// The value of the items this player has equipped, that are not in the
+    /// scrapbook. Epics are weighted according to the epic mode
+    pub fn missing_in(
+        &self,
+        scrapbook: &HashSet<EquipmentIdent>,
+        epic_mode: EpicMode,
+    ) -> usize {
+        self.equipment
+            .iter()
+            .filter(|a| epic_mode.counts(a) && !scrapbook.contains(a))
+            .map(|a| epic_mode.weight_of(a))
+            .sum()
+    }
+
+    /// The amount of normal & epic items this player has equipped, that are
+    /// not in the scrapbook
+    pub fn missing_split(
+        &self,
+        scrapbook: &HashSet<EquipmentIdent>,
+    ) -> (usize, usize) {
+        let mut res = (0, 0);
+        for item in self.equipment.iter().filter(|a| !scrapbook.contains(a)) {
+            match item.model_id < 100 {
+                true => res.0 += 1,
+                false => res.1 += 1,
+            }
+        }
+        res
     }
 }
 
@@ -307,7 +475,45 @@ impl Application for Helper {
     type Flags = Args;
 
     fn new(flags: Args) -> (Self, iced::Command<Self::Message>) {
-        let config = Config::restore().unwrap_or_default();
+        if let Err(e) = vault::unlock_from_env() {
+            error!("Could not unlock the credential vault: {e}");
+        }
+        let is_daemon = matches!(flags.sub, Some(CLICommand::Run));
+        let (config, config_error) = match Config::restore() {
+            Ok(config) => (config, None),
+            Err(e) => {
+                error!("Could not read the config: {e}");
+                // Nothing may replace the config on disk, until we know,
+                // that it is safe to do so
+                Config::block_writes(true);
+                let mut message = format!("Could not read the config: {e}");
+                let recoverable = match &e {
+                    // A newer helper can still read this config, so it is
+                    // never replaced
+                    ConfigError::TooNew(_) => false,
+                    _ => match Config::preserve_broken() {
+                        Ok(path) => {
+                            message.push_str(&format!(
+                                ". It has been saved as {path}"
+                            ));
+                            true
+                        }
+                        Err(e) => {
+                            error!("Could not back up the config: {e}");
+                            false
+                        }
+                    },
+                };
+                if !recoverable {
+                    message.push_str(". Changes will not be saved");
+                }
+                let issue = ConfigIssue {
+                    message,
+                    recoverable,
+                };
+                (Config::default(), Some(issue))
+            }
+        };
         let mut helper = Helper {
             servers: Default::default(),
             login_state: LoginState {
@@ -321,6 +527,9 @@ impl Application for Helper {
                 server: "f1.sfgame.net".to_string(),
                 error: None,
                 remember_me: true,
+                vault_password: String::new(),
+                vault_error: None,
+                sso_notice: None,
                 active_sso: vec![],
                 import_que: vec![],
                 google_sso: Arc::new(Mutex::new(SSOStatus::Initializing)),
@@ -331,6 +540,11 @@ impl Application for Helper {
             class_images: ClassImages::new(),
             config,
             cli_crawling: None,
+            alerts: vec![],
+            souls_alerts: vec![],
+            config_error,
+            new_profile: String::new(),
+            metrics: Metrics::default(),
         };
 
         let fetch_update =
@@ -343,41 +557,43 @@ impl Application for Helper {
             concurrency,
             threads,
             servers,
+            options,
         }) = flags.sub
         {
-            let mut info = CLICrawling {
+            let info = CLICrawling {
                 todo_servers: Vec::new(),
                 mbp: MultiProgress::new(),
                 active: concurrency,
                 threads,
+                options,
             };
 
-            if let Some(servers) = servers.urls {
-                info.todo_servers = servers;
-
-                for _ in 0..concurrency {
-                    commands.push(Command::perform(async {}, move |_| {
-                        Message::NextCLICrawling
-                    }))
-                }
-            } else if servers.all {
-                let c = Command::perform(
+            let c = if let Some(servers) = servers.urls {
+                Command::perform(async {}, move |_| Message::CrawlAllRes {
+                    servers: Some(servers),
+                    concurrency,
+                })
+            } else if let Some(path) = servers.file {
+                Command::perform(read_server_list(path), move |servers| {
+                    Message::CrawlAllRes {
+                        servers,
+                        concurrency,
+                    }
+                })
+            } else {
+                Command::perform(
                     async {
                         ServerLookup::fetch().await.ok().map(|a| {
-                            a.all()
-                                .into_iter()
-                                .map(|a| a.to_string())
-                                .filter(|a| a != "https://speed.sfgame.net/")
-                                .collect()
+                            a.all().into_iter().map(|a| a.to_string()).collect()
                         })
                     },
                     move |servers| Message::CrawlAllRes {
                         servers,
                         concurrency,
                     },
-                );
-                commands.push(c);
-            }
+                )
+            };
+            commands.push(c);
             helper.cli_crawling = Some(info);
         }
         commands.push(
@@ -385,54 +601,20 @@ impl Application for Helper {
                 .map(Message::FontLoaded),
         );
 
-        let mut loading = 0;
-
-        for acc in &helper.config.accounts {
-            match acc {
-                AccountConfig::Regular { config, .. } => {
-                    if config.login {
-                        let acc = acc.clone();
-                        loading += 1;
-                        commands.push(Command::perform(
-                            async move {
-                                sleep(Duration::from_millis(
-                                    (loading - 1) * 200,
-                                ))
-                                .await
-                            },
-                            move |_| Message::Login {
-                                account: acc,
-                                auto_login: true,
-                            },
-                        ));
-                    }
-                }
-                AccountConfig::SF { characters, .. } => {
-                    if characters.iter().any(|a| a.config.login) {
-                        loading += 1;
-                        let acc = acc.clone();
-                        commands.push(Command::perform(
-                            async move {
-                                sleep(Duration::from_millis(
-                                    (loading - 1) * 200,
-                                ))
-                                .await
-                            },
-                            move |_| Message::Login {
-                                account: acc,
-                                auto_login: true,
-                            },
-                        ));
-                    }
-                }
-            }
-        }
-
-        if loading > 0 {
-            helper.current_view = View::Overview {
-                selected: Default::default(),
-                action: Default::default(),
-            };
+        if !helper.config.is_locked() {
+            commands.extend(helper.auto_login());
+        } else if !vault::exists() {
+            let error = vault::VaultError::Missing.to_string();
+            error!("{error}");
+            helper.login_state.error = Some(error);
+        } else if is_daemon {
+            error!(
+                "The credentials are locked. Set {} or {} to unlock them",
+                vault::PASSWORD_ENV,
+                vault::PASSWORD_FD_ENV
+            );
+        } else {
+            helper.current_view = View::Unlock;
         }
 
         (helper, Command::batch(commands))
@@ -480,6 +662,8 @@ impl Application for Helper {
             AutoLure(AccountIdent),
             SSOCheck(SSOProvider),
             Crawling(usize, ServerID),
+            Watchlist(u64),
+            Api(u16, String),
         }
 
         let mut subs = vec![];
@@ -493,6 +677,19 @@ impl Application for Helper {
         );
         subs.push(subscription);
 
+        if !self.config.watchlist.is_empty() {
+            let interval = self.config.watch_interval.max(1);
+            let subscription = subscription::unfold(
+                SubIdent::Watchlist(interval),
+                (),
+                move |a: ()| async move {
+                    sleep(Duration::from_secs(interval * 60)).await;
+                    (Message::WatchlistTick, a)
+                },
+            );
+            subs.push(subscription);
+        }
+
         for (server_id, server) in &self.servers.0 {
             for acc in server.accounts.values() {
                 if self.config.auto_poll {
@@ -569,6 +766,9 @@ impl Application for Helper {
             (&self.login_state.steam_sso, SSOProvider::Steam),
             (&self.login_state.google_sso, SSOProvider::Google),
         ] {
+            if !arc.lock().unwrap().is_active() {
+                continue;
+            }
             let arc = arc.clone();
             let subscription = subscription::unfold(
                 SubIdent::SSOCheck(prov),
@@ -576,24 +776,18 @@ impl Application for Helper {
                     status: arc,
                     provider: prov,
                 },
-                move |a: SSOValidator| async move {
-                    let msg = match a.check().await {
-                        Ok(Some((chars, name))) => {
-                            let chars = chars.into_iter().flatten().collect();
-                            Message::SSOSuccess {
-                                auth_name: name,
-                                chars,
-                                provider: prov,
-                            }
-                        }
-                        Ok(None) => Message::SSORetry,
-                        Err(e) => Message::SSOAuthError {
-                            _error: e.to_string(),
-                        },
-                    };
+                move |a: SSOValidator| async move { (a.check().await, a) },
+            );
+            subs.push(subscription);
+        }
 
-                    (msg, a)
-                },
+        if self.config.api.enabled {
+            let port = self.config.api.port;
+            let token = self.config.api.token.clone();
+            let subscription = subscription::channel(
+                SubIdent::Api(port, token.clone()),
+                100,
+                move |output| api::serve(port, token, output),
             );
             subs.push(subscription);
         }
@@ -603,12 +797,154 @@ impl Application for Helper {
 }
 
 impl Helper {
+    /// Saves the characters of a Google, or Steam account together with their
+    /// sessions, so that they can log in again after a restart. These
+    /// accounts have no password, so without a cached session, they have to
+    /// go through the browser login again, which the user is told about
+    fn remember_sso(
+        &mut self,
+        name: String,
+        provider: SSOService,
+        chars: &[sf_api::session::Session],
+    ) {
+        let existing = self.config.accounts.iter_mut().find_map(|a| match a {
+            AccountConfig::SSO {
+                name: s_name,
+                provider: s_provider,
+                characters,
+            } if *s_provider == provider && *s_name == name => Some(characters),
+            _ => None,
+        });
+        let characters = match existing {
+            Some(characters) => characters,
+            None => {
+                self.config.accounts.push(AccountConfig::SSO {
+                    name,
+                    provider,
+                    characters: vec![],
+                });
+                let Some(AccountConfig::SSO { characters, .. }) =
+                    self.config.accounts.last_mut()
+                else {
+                    return;
+                };
+                characters
+            }
+        };
+
+        let mut new = vec![];
+        let mut uncached = 0;
+        for char in chars {
+            if !session_cache::store(char) {
+                uncached += 1;
+            }
+            new.push(SFCharIdent {
+                name: char.username().trim().to_lowercase(),
+                server: ServerIdent::new(char.server_url().as_str()).url,
+            });
+        }
+        characters.retain(|a| {
+            new.iter().any(|n| {
+                n.name == a.ident.name.trim().to_lowercase()
+                    && ServerIdent::new(&n.server)
+                        == ServerIdent::new(&a.ident.server)
+            })
+        });
+        for ident in new {
+            let known = characters.iter().any(|a| {
+                a.ident.name.trim().to_lowercase() == ident.name
+                    && ServerIdent::new(&a.ident.server)
+                        == ServerIdent::new(&ident.server)
+            });
+            if !known {
+                characters.push(SFAccCharacter {
+                    ident,
+                    config: CharacterConfig::default(),
+                });
+            }
+        }
+        _ = self.config.write();
+
+        self.login_state.sso_notice = (uncached > 0).then(|| {
+            format!(
+                "The {provider} account has been saved, but the sessions of \
+                 {uncached} character(s) could not be kept. Sessions are only \
+                 kept with an unlocked credential vault, so you will have to \
+                 log in with {provider} again after a restart"
+            )
+        });
+    }
+
+    /// Logs in all saved accounts, that have `login` enabled
+    fn auto_login(&mut self) -> Vec<Command<Message>> {
+        let mut commands = vec![];
+        let mut loading = 0;
+
+        for acc in &self.config.accounts {
+            match acc {
+                AccountConfig::Regular { config, .. } => {
+                    if config.login {
+                        let acc = acc.clone();
+                        loading += 1;
+                        commands.push(Command::perform(
+                            async move {
+                                sleep(Duration::from_millis(
+                                    (loading - 1) * 200,
+                                ))
+                                .await
+                            },
+                            move |_| Message::Login {
+                                account: acc,
+                                auto_login: true,
+                            },
+                        ));
+                    }
+                }
+                AccountConfig::SF { characters, .. }
+                | AccountConfig::SSO { characters, .. } => {
+                    if characters.iter().any(|a| a.config.login) {
+                        loading += 1;
+                        let acc = acc.clone();
+                        commands.push(Command::perform(
+                            async move {
+                                sleep(Duration::from_millis(
+                                    (loading - 1) * 200,
+                                ))
+                                .await
+                            },
+                            move |_| Message::Login {
+                                account: acc,
+                                auto_login: true,
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+
+        if loading > 0 {
+            self.current_view = View::Overview {
+                selected: Default::default(),
+                action: Default::default(),
+            };
+        }
+        commands
+    }
+
+    /// Starts crawling a server from the command line. Continues from
+    /// `restored`, if the crawl is resumed
     fn force_init_crawling(
         &mut self,
         url: &str,
         threads: usize,
         pb: ProgressBar,
+        restored: Option<Box<RestoreData>>,
     ) -> Option<Command<Message>> {
+        let options = self.cli_crawling.as_ref().map(|a| &a.options);
+        let min_level = options.and_then(|a| a.min_level);
+        let max_level = options.and_then(|a| a.max_level);
+        let order = options.and_then(|a| a.order);
+
         let ident = ServerIdent::new(url);
         let connection = ServerConnection::new(url)?;
         pb.enable_steady_tick(Duration::from_millis(30));
@@ -620,37 +956,206 @@ impl Helper {
             Some(pb.clone()),
         );
 
-        let que_id = QueID::new();
-
-        let que = WorkerQue {
-            que_id,
-            todo_pages: Default::default(),
-            todo_accounts: Default::default(),
-            invalid_pages: Default::default(),
-            invalid_accounts: Default::default(),
-            in_flight_pages: Default::default(),
-            in_flight_accounts: Default::default(),
-            order: Default::default(),
-            lvl_skipped_accounts: Default::default(),
-            min_level: Default::default(),
-            max_level: 9999,
-            self_init: true,
-        };
+        server.crawling = match restored {
+            Some(restored) => restored.into_status(),
+            None => {
+                let que_id = QueID::new();
+
+                let que = WorkerQue {
+                    que_id,
+                    todo_pages: Default::default(),
+                    todo_accounts: Default::default(),
+                    invalid_pages: Default::default(),
+                    invalid_accounts: Default::default(),
+                    in_flight_pages: Default::default(),
+                    in_flight_accounts: Default::default(),
+                    order: Default::default(),
+                    lvl_skipped_accounts: Default::default(),
+                    min_level: Default::default(),
+                    max_level: 9999,
+                    self_init: true,
+                };
 
-        server.crawling = CrawlingStatus::Crawling {
-            que_id,
-            threads: 0,
-            que: Arc::new(Mutex::new(que)),
-            player_info: Default::default(),
-            equipment: Default::default(),
-            naked: Default::default(),
-            last_update: Local::now(),
-            crawling_session: None,
-            recent_failures: Default::default(),
+                CrawlingStatus::Crawling {
+                    que_id,
+                    threads: 0,
+                    que: Arc::new(Mutex::new(que)),
+                    player_info: Default::default(),
+                    equipment: Default::default(),
+                    naked: Default::default(),
+                    last_update: Local::now(),
+                    crawling_session: None,
+                    recent_failures: Default::default(),
+                }
+            }
         };
+
+        if let CrawlingStatus::Crawling { que, .. } = &server.crawling {
+            let mut que = que.lock().unwrap();
+            if min_level.is_some() || max_level.is_some() {
+                let min = min_level.unwrap_or(que.min_level);
+                let max = max_level.unwrap_or(que.max_level);
+                que.set_level_range(min, max);
+            }
+            if let Some(order) = order {
+                que.order = order;
+                order.apply_order(&mut que.todo_pages);
+            }
+        }
         Some(server.set_threads(threads, &self.config.base_name))
     }
 
+    /// Starts crawling a server from the command line
+    fn start_cli_crawling(
+        &mut self,
+        url: String,
+        pb: ProgressBar,
+        restored: Option<Box<RestoreData>>,
+    ) -> Command<Message> {
+        let resumed = restored.is_some();
+        let json = self.cli_crawling.as_ref().is_some_and(|a| a.options.json);
+        let threads = self.cli_crawling.as_ref().map_or(1, |a| a.threads);
+        match self.force_init_crawling(&url, threads, pb.clone(), restored) {
+            Some(command) => {
+                if json {
+                    CrawlEvent::Started {
+                        server: &url,
+                        resumed,
+                    }
+                    .print();
+                }
+                command
+            }
+            None => {
+                if json {
+                    CrawlEvent::Failed {
+                        server: &url,
+                        error: "Could not init crawling",
+                    }
+                    .print();
+                }
+                pb.println(format!("Could not init crawling on: {url}"));
+                pb.finish_and_clear();
+                Command::perform(async {}, |_| Message::NextCLICrawling)
+            }
+        }
+    }
+
+    /// Stops crawling a server from the command line, once it reached the
+    /// time, or request limit
+    fn check_cli_limits(
+        &mut self,
+        server_id: ServerID,
+    ) -> Option<Command<Message>> {
+        let options = &self.cli_crawling.as_ref()?.options;
+        let server = self.servers.get(&server_id)?;
+        let pb = server.headless_progress.as_ref()?;
+        let CrawlingStatus::Crawling { threads, .. } = &server.crawling else {
+            return None;
+        };
+        if *threads == 0 {
+            // We already stopped
+            return None;
+        }
+
+        let reason = if options
+            .max_minutes
+            .is_some_and(|a| pb.elapsed() >= Duration::from_secs(a * 60))
+        {
+            "max_minutes"
+        } else if options
+            .max_requests
+            .is_some_and(|a| self.metrics.crawler_requests(server_id) >= a)
+        {
+            "max_requests"
+        } else {
+            return None;
+        };
+
+        if options.json {
+            CrawlEvent::LimitReached {
+                server: &server.ident.url,
+                reason,
+            }
+            .print();
+        }
+        pb.println(format!("Reached {reason} on {}", server.ident.url));
+        Some(self.write_cli_backup(server_id))
+    }
+
+    /// Stops the crawler & writes the backup of a server crawled from the
+    /// command line. The next server is started, once the backup is written
+    fn write_cli_backup(&mut self, server_id: ServerID) -> Command<Message> {
+        let Some(server) = self.servers.get_mut(&server_id) else {
+            return Command::none();
+        };
+        let CrawlingStatus::Crawling {
+            threads,
+            player_info,
+            que,
+            ..
+        } = &mut server.crawling
+        else {
+            return Command::none();
+        };
+        if *threads == 0 {
+            // The backup is already being written
+            return Command::none();
+        }
+        *threads = 0;
+
+        let backup = que.lock().unwrap().create_backup(player_info);
+        let ident = server.ident.ident.to_string();
+        let id = server.ident.id;
+
+        Command::perform(
+            async move { backup.write(&ident).await },
+            move |res| Message::BackupRes {
+                server: id,
+                error: res.err().map(|a| a.to_string()),
+            },
+        )
+    }
+
+    /// Writes the backups of all servers, that are being crawled, because
+    /// switching the profile exits this helper. The switch happens, once all
+    /// of them are written
+    fn switch_profile(&mut self, name: Option<String>) -> Command<Message> {
+        // A config, that could not be read, must not be replaced
+        if self.config_error.is_none() {
+            _ = self.config.write();
+        }
+        let backups: Vec<_> = self
+            .servers
+            .0
+            .values()
+            .filter_map(|server| {
+                let CrawlingStatus::Crawling {
+                    que, player_info, ..
+                } = &server.crawling
+                else {
+                    return None;
+                };
+                let backup = que.lock().unwrap().create_backup(player_info);
+                Some((backup, server.ident.ident.to_string()))
+            })
+            .collect();
+
+        Command::perform(
+            async move {
+                let mut saved = true;
+                for (backup, ident) in backups {
+                    if let Err(e) = backup.write(&ident).await {
+                        error!("Could not save the progress of {ident}: {e}");
+                        saved = false;
+                    }
+                }
+                saved
+            },
+            move |saved| Message::ProfileSaved { name, saved },
+        )
+    }
+
     fn has_accounts(&self) -> bool {
         self.servers.0.iter().any(|a| !a.1.accounts.is_empty())
     }
@@ -696,21 +1201,43 @@ impl Helper {
         let result_limit = 50;
 
         if let Some(si) = &mut account.scrapbook_info {
+            let mut missing_items: Vec<_> = equipment
+                .iter()
+                .filter(|a| si.epic_mode.counts(a.0))
+                .filter(|a| !si.scrapbook.items.contains(a.0))
+                .map(|(ident, players)| (*ident, players.len()))
+                .collect();
+            missing_items.sort_by_key(|(ident, _)| {
+                (
+                    ident.typ as u8,
+                    ident.class.map(|a| a as u8),
+                    ident.model_id,
+                    ident.color,
+                )
+            });
+            si.missing_available = missing_items.len();
+            si.missing_items = missing_items;
+
             let per_player_counts = calc_per_player_count(
                 player_info, equipment, &si.scrapbook.items, si,
                 self.config.blacklist_threshold,
             );
-            let mut best_players = find_best(
-                &per_player_counts, player_info, result_limit, &invalid,
+            let best_players = rank_targets(
+                si, &per_player_counts, equipment, player_info, result_limit,
+                &invalid,
             );
-
-            best_players.sort_by(|a, b| {
-                b.missing
-                    .cmp(&a.missing)
-                    .then(a.info.stats.cmp(&b.info.stats))
-                    .then(a.info.level.cmp(&b.info.level))
-            });
-
+            si.missing_attackable = si
+                .missing_items
+                .iter()
+                .filter(|(item, _)| {
+                    equipment.get(item).is_some_and(|carriers| {
+                        carriers
+                            .iter()
+                            .any(|a| per_player_counts.contains_key(a))
+                    })
+                })
+                .count();
+            si.per_player_counts = per_player_counts;
             si.best = best_players;
 
             for target in &si.best {
@@ -726,24 +1253,46 @@ impl Helper {
         };
 
         if let Some(ui) = &mut account.underworld_info {
-            ui.best.clear();
-            'a: for (_, players) in naked.range(..=ui.max_level).rev() {
-                for player in players.iter() {
-                    if ui.best.len() >= result_limit {
-                        break 'a;
-                    }
-                    let Some(info) = player_info.get(player) else {
-                        continue;
-                    };
-                    if info.is_old()
-                        && !lock.todo_accounts.contains(&info.name)
-                        && !lock.invalid_accounts.contains(&info.name)
-                        && !lock.in_flight_accounts.contains(&info.name)
-                    {
-                        has_old = true;
-                        lock.todo_accounts.push(info.name.to_string())
-                    }
-                    ui.best.push(info.to_owned());
+            let stats = account.lure_stats.get_or_insert_with(|| {
+                LureStats::new(&account.history.entries, player_info)
+            });
+            let unit_level = avg_unit_level(&ui.underworld);
+
+            // We have to look at every candidate, because the chance does not
+            // only depend on the level
+            let mut candidates: Vec<_> = naked
+                .range(..=ui.max_level)
+                .flat_map(|(_, players)| players.iter())
+                .filter_map(|a| player_info.get(a))
+                .filter(|info| {
+                    ui.filter.matches(info)
+                        && !stats.is_blacklisted(
+                            info.uid, self.config.blacklist_threshold,
+                        )
+                        && !stats
+                            .on_cooldown(info.uid, self.config.lure_cooldown)
+                })
+                .map(|info| (stats.chance(info, unit_level), info))
+                .collect();
+            candidates.sort_by(|a, b| {
+                b.0.total_cmp(&a.0).then(b.1.level.cmp(&a.1.level))
+            });
+            candidates.truncate(result_limit);
+
+            ui.chances = candidates
+                .iter()
+                .map(|(chance, a)| (a.uid, *chance))
+                .collect();
+            ui.best = candidates.into_iter().map(|a| a.1.to_owned()).collect();
+
+            for info in &ui.best {
+                if info.is_old()
+                    && !lock.todo_accounts.contains(&info.name)
+                    && !lock.invalid_accounts.contains(&info.name)
+                    && !lock.in_flight_accounts.contains(&info.name)
+                {
+                    has_old = true;
+                    lock.todo_accounts.push(info.name.to_string())
                 }
             }
         }
@@ -777,11 +1326,12 @@ pub fn calc_per_player_count(
     per_player_counts.reserve(player_info.len());
 
     for (eq, players) in equipment.iter() {
-        if scrapbook.contains(eq) || eq.model_id >= 100 {
+        if scrapbook.contains(eq) || !si.epic_mode.counts(eq) {
             continue;
         }
+        let weight = si.epic_mode.weight_of(eq);
         for player in players.iter() {
-            *per_player_counts.entry(*player).or_insert(0) += 1;
+            *per_player_counts.entry(*player).or_insert(0) += weight;
         }
     }
 
@@ -803,11 +1353,94 @@ pub fn calc_per_player_count(
                 return false;
             }
         }
-        true
+        si.filter.matches(info)
     });
+    if si.filter.min_missing > 1 {
+        per_player_counts.retain(|_, count| *count >= si.filter.min_missing);
+    }
     per_player_counts
 }
 
+/// Weights every missing item by how many attackable players have it
+/// equipped. An item, that only a single attackable player has, is worth a
+/// full point, whilst an item, that 100 attackable players have, only adds
+/// 0.01 to the score of each of them. Players, that are not in
+/// `per_player_counts` (or have a count of 0) are considered not attackable
+pub fn calc_rarity_scores(
+    per_player_counts: &IntMap<u32, usize>,
+    equipment: &HashMap<
+        EquipmentIdent,
+        HashSet<u32, ahash::RandomState>,
+        ahash::RandomState,
+    >,
+    scrapbook: &HashSet<EquipmentIdent>,
+    epic_mode: EpicMode,
+) -> IntMap<u32, f32> {
+    let is_attackable =
+        |a: &u32| per_player_counts.get(a).is_some_and(|a| *a > 0);
+
+    let mut scores: IntMap<u32, f32> = IntMap::default();
+    for (eq, players) in equipment.iter() {
+        if scrapbook.contains(eq) || !epic_mode.counts(eq) {
+            continue;
+        }
+        let attackable = players.iter().filter(|a| is_attackable(a)).count();
+        if attackable == 0 {
+            continue;
+        }
+        let weight = epic_mode.weight_of(eq) as f32 / attackable as f32;
+        for player in players.iter().filter(|a| is_attackable(a)) {
+            *scores.entry(*player).or_insert(0.0) += weight;
+        }
+    }
+    scores
+}
+
+/// The score of a single target according to the scoring of the scrapbook.
+/// Matches the score `rank_targets` would give the target, without scoring
+/// every other player as well
+pub fn score_target(
+    si: &ScrapbookInfo,
+    info: &CharacterInfo,
+    missing: usize,
+    equipment: &HashMap<
+        EquipmentIdent,
+        HashSet<u32, ahash::RandomState>,
+        ahash::RandomState,
+    >,
+) -> f32 {
+    if missing == 0 {
+        return 0.0;
+    }
+    let rarity = || {
+        let is_attackable =
+            |a: &&u32| si.per_player_counts.get(*a).is_some_and(|a| *a > 0);
+        info.equipment
+            .iter()
+            .filter(|eq| {
+                !si.scrapbook.items.contains(eq) && si.epic_mode.counts(eq)
+            })
+            .filter_map(|eq| {
+                let attackable =
+                    equipment.get(eq)?.iter().filter(is_attackable).count();
+                (attackable > 0).then(|| {
+                    si.epic_mode.weight_of(eq) as f32 / attackable as f32
+                })
+            })
+            .sum()
+    };
+    match (si.scoring, &si.script) {
+        (TargetScoring::Missing, _) | (TargetScoring::Script, None) => {
+            missing as f32
+        }
+        (TargetScoring::Rarity, _) => rarity(),
+        (TargetScoring::Script, Some(script)) => script
+            .scorer(si)
+            .score(info, missing, rarity())
+            .unwrap_or(missing as f32),
+    }
+}
+
 macro_rules! impl_unique_id {
     ($type:ty) => {
         impl $type {
@@ -900,9 +1533,12 @@ impl ServerInfo {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct AttackTarget {
     missing: usize,
+    /// The value of this target according to the selected scoring. Equal to
+    /// `missing` for the default scoring
+    score: f32,
     info: CharacterInfo,
 }
 impl AttackTarget {
@@ -917,26 +1553,28 @@ fn find_best(
     max_out: usize,
     invalid: &HashSet<&str>,
 ) -> Vec<AttackTarget> {
-    // Prune the counts to make computation faster
+    // Prune the counts to make computation faster. The counts are weighted
+    // in epic mode, so they are not limited to the 10 item slots
     let mut max = 1;
-    let mut counts = [(); 10].map(|_| vec![]);
+    let mut counts: BTreeMap<usize, Vec<u32>> = BTreeMap::new();
     for (player, count) in per_player_counts.iter().map(|a| (*a.0, *a.1)) {
         if max_out == 1 && count < max || count == 0 {
             continue;
         }
         max = max.max(count);
-        counts[(count - 1).clamp(0, 9)].push(player);
+        counts.entry(count).or_default().push(player);
     }
 
     let mut best_players = Vec::new();
-    for (count, players) in counts.iter().enumerate().rev() {
+    for (count, players) in counts.iter().rev() {
         best_players.extend(
             players
                 .iter()
                 .flat_map(|a| player_info.get(a))
                 .filter(|a| !invalid.contains(&a.name.as_str()))
                 .map(|a| AttackTarget {
-                    missing: count + 1,
+                    missing: *count,
+                    score: *count as f32,
                     info: a.to_owned(),
                 }),
         );
@@ -944,12 +1582,257 @@ fn find_best(
             break;
         }
     }
-    best_players.sort_by(|a, b| b.cmp(a));
+    best_players.sort_by(|a, b| {
+        b.missing.cmp(&a.missing).then_with(|| b.info.cmp(&a.info))
+    });
+    best_players.truncate(max_out);
+
+    best_players
+}
+
+fn find_best_rare(
+    per_player_counts: &IntMap<u32, usize>,
+    scores: &IntMap<u32, f32>,
+    player_info: &IntMap<u32, CharacterInfo>,
+    max_out: usize,
+    invalid: &HashSet<&str>,
+) -> Vec<AttackTarget> {
+    let mut best_players: Vec<_> = scores
+        .iter()
+        .filter(|a| *a.1 > 0.0)
+        .flat_map(|(player, score)| {
+            let info = player_info.get(player)?;
+            if invalid.contains(&info.name.as_str()) {
+                return None;
+            }
+            Some(AttackTarget {
+                missing: per_player_counts.get(player).copied()?,
+                score: *score,
+                info: info.to_owned(),
+            })
+        })
+        .collect();
+
+    best_players.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.missing.cmp(&a.missing))
+            .then_with(|| b.info.cmp(&a.info))
+    });
     best_players.truncate(max_out);
 
     best_players
 }
 
+/// The amount of players with the most missing items, that are scored by a
+/// script. Scoring every player on the server would take far too long
+const SCRIPT_CANDIDATES: usize = 500;
+
+/// Ranks the players with the most missing items by the score, that the
+/// script of the character returns for them
+fn find_best_scripted(
+    per_player_counts: &IntMap<u32, usize>,
+    rarity: &IntMap<u32, f32>,
+    player_info: &IntMap<u32, CharacterInfo>,
+    max_out: usize,
+    invalid: &HashSet<&str>,
+    scorer: &mut Scorer,
+) -> Result<Vec<AttackTarget>, String> {
+    let mut best_players = find_best(
+        per_player_counts,
+        player_info,
+        SCRIPT_CANDIDATES.max(max_out),
+        invalid,
+    );
+    for target in &mut best_players {
+        let rarity = rarity.get(&target.info.uid).copied().unwrap_or_default();
+        target.score = scorer.score(&target.info, target.missing, rarity)?;
+    }
+
+    best_players.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.missing.cmp(&a.missing))
+            .then_with(|| b.info.cmp(&a.info))
+    });
+    best_players.truncate(max_out);
+
+    Ok(best_players)
+}
+
+/// Finds the best targets according to the scoring of the scrapbook. If the
+/// custom script fails, the targets are ranked by missing items and the
+/// error is stored in the scrapbook info
+pub fn rank_targets(
+    si: &mut ScrapbookInfo,
+    per_player_counts: &IntMap<u32, usize>,
+    equipment: &HashMap<
+        EquipmentIdent,
+        HashSet<u32, ahash::RandomState>,
+        ahash::RandomState,
+    >,
+    player_info: &IntMap<u32, CharacterInfo>,
+    result_limit: usize,
+    invalid: &HashSet<&str>,
+) -> Vec<AttackTarget> {
+    match si.scoring {
+        TargetScoring::Missing => {
+            let mut best_players = find_best(
+                per_player_counts, player_info, result_limit, invalid,
+            );
+            best_players.sort_by(|a, b| {
+                b.missing
+                    .cmp(&a.missing)
+                    .then(a.info.stats.cmp(&b.info.stats))
+                    .then(a.info.level.cmp(&b.info.level))
+            });
+            best_players
+        }
+        TargetScoring::Rarity => {
+            let scores = calc_rarity_scores(
+                per_player_counts, equipment, &si.scrapbook.items, si.epic_mode,
+            );
+            find_best_rare(
+                per_player_counts, &scores, player_info, result_limit, invalid,
+            )
+        }
+        TargetScoring::Script => {
+            let scores = calc_rarity_scores(
+                per_player_counts, equipment, &si.scrapbook.items, si.epic_mode,
+            );
+            let res = si.script.as_ref().map(|script| {
+                find_best_scripted(
+                    per_player_counts,
+                    &scores,
+                    player_info,
+                    result_limit,
+                    invalid,
+                    &mut script.scorer(si),
+                )
+            });
+            match res {
+                Some(Ok(best_players)) => {
+                    si.script_error = None;
+                    best_players
+                }
+                Some(Err(e)) => {
+                    si.script_error = Some(e);
+                    find_best(
+                        per_player_counts, player_info, result_limit, invalid,
+                    )
+                }
+                // The script did not compile. The error is already set
+                None => find_best(
+                    per_player_counts, player_info, result_limit, invalid,
+                ),
+            }
+        }
+    }
+}
+
+/// The order in which the targets should be attacked to collect as many
+/// items as possible, starting with `best`. Every target is assumed to be
+/// won against, so that the following targets only count the items, that
+/// are still missing after that
+pub fn calc_battle_order(
+    mut best: Option<AttackTarget>,
+    player_info: &IntMap<u32, CharacterInfo>,
+    equipment: &HashMap<
+        EquipmentIdent,
+        HashSet<u32, ahash::RandomState>,
+        ahash::RandomState,
+    >,
+    si: &ScrapbookInfo,
+    invalid: &HashSet<&str>,
+    blacklist_th: usize,
+) -> Vec<String> {
+    let mut scrapbook = si.scrapbook.items.clone();
+
+    let mut per_player_counts = calc_per_player_count(
+        player_info, equipment, &scrapbook, si, blacklist_th,
+    );
+    let mut scores = match si.scoring {
+        TargetScoring::Missing => IntMap::default(),
+        TargetScoring::Rarity | TargetScoring::Script => calc_rarity_scores(
+            &per_player_counts, equipment, &scrapbook, si.epic_mode,
+        ),
+    };
+
+    let mut scorer = match si.scoring {
+        TargetScoring::Script => si.script.as_ref().map(|a| a.scorer(si)),
+        _ => None,
+    };
+
+    let mut target_list = Vec::new();
+    let mut loop_count = 0;
+
+    while let Some(AttackTarget { missing, info, .. }) = best {
+        if loop_count > 300 || missing == 0 {
+            break;
+        }
+        loop_count += 1;
+
+        for eq in &info.equipment {
+            if scrapbook.contains(eq) || !si.epic_mode.counts(eq) {
+                continue;
+            }
+            let weight = si.epic_mode.weight_of(eq);
+            let Some(players) = equipment.get(eq) else {
+                continue;
+            };
+            // The item no longer adds to the rarity score of anyone. A player
+            // only stops being attackable, once all of their items are found,
+            // so the shares of the other items stay the same
+            let attackable = players
+                .iter()
+                .filter(|a| per_player_counts.get(a).is_some_and(|a| *a > 0))
+                .count();
+            if attackable > 0 {
+                let share = weight as f32 / attackable as f32;
+                for player in players {
+                    if let Some(score) = scores.get_mut(player) {
+                        *score -= share;
+                    }
+                }
+            }
+            // We decrease the new equipment count of all players, that have
+            // the same item as the one we just "found"
+            for player in players {
+                let ppc = per_player_counts.entry(*player).or_insert(1);
+                *ppc = ppc.saturating_sub(weight);
+                if *ppc == 0 {
+                    scores.remove(player);
+                }
+            }
+        }
+
+        scrapbook.extend(info.equipment);
+        target_list.push(info.name);
+        let best_players = match si.scoring {
+            TargetScoring::Missing => {
+                find_best(&per_player_counts, player_info, 1, invalid)
+            }
+            TargetScoring::Rarity => find_best_rare(
+                &per_player_counts, &scores, player_info, 1, invalid,
+            ),
+            TargetScoring::Script => scorer
+                .as_mut()
+                .and_then(|scorer| {
+                    find_best_scripted(
+                        &per_player_counts, &scores, player_info, 1, invalid,
+                        scorer,
+                    )
+                    .ok()
+                })
+                .unwrap_or_else(|| {
+                    find_best(&per_player_counts, player_info, 1, invalid)
+                }),
+        };
+        best = best_players.into_iter().next();
+    }
+    target_list
+}
+
 fn top_bar(
     center: Element<Message>,
     back: Option<Message>,
@@ -995,11 +1878,10 @@ pub fn handle_new_char_info(
     >,
     player_info: &mut IntMap<u32, CharacterInfo>,
     naked: &mut BTreeMap<u16, IntSet<u32>>,
+    rules: &LureRules,
 ) {
     let player_entry = player_info.entry(char.uid);
 
-    const EQ_CUTOFF: usize = 4;
-
     match player_entry {
         Entry::Occupied(mut old) => {
             // We have already seen this player. We have to remove the old info
@@ -1020,13 +1902,11 @@ pub fn handle_new_char_info(
                         HashSet::from_iter([char.uid].into_iter())
                     });
             }
-            if old_info.equipment.len() < EQ_CUTOFF {
-                naked.entry(old_info.level).and_modify(|a| {
-                    a.remove(&old_info.uid);
-                });
-            }
+            naked.entry(old_info.level).and_modify(|a| {
+                a.remove(&old_info.uid);
+            });
 
-            if char.equipment.len() < EQ_CUTOFF {
+            if rules.matches(&char) {
                 naked.entry(char.level).or_default().insert(char.uid);
             }
             old.insert(char);
@@ -1042,7 +1922,7 @@ pub fn handle_new_char_info(
                         HashSet::from_iter([char.uid].into_iter())
                     });
             }
-            if char.equipment.len() < EQ_CUTOFF && char.level >= 100 {
+            if rules.matches(&char) {
                 naked.entry(char.level).or_default().insert(char.uid);
             }
             v.insert(char);
@@ -1050,7 +1930,20 @@ pub fn handle_new_char_info(
     }
 }
 
-fn get_log_config(is_headless: bool) -> log4rs::Config {
+/// Builds the index of all lure targets from scratch. Used, when the lure
+/// rules change, so that we do not have to crawl everything again
+pub fn rebuild_naked(
+    player_info: &IntMap<u32, CharacterInfo>,
+    rules: &LureRules,
+) -> BTreeMap<u16, IntSet<u32>> {
+    let mut naked: BTreeMap<u16, IntSet<u32>> = BTreeMap::new();
+    for info in player_info.values().filter(|a| rules.matches(a)) {
+        naked.entry(info.level).or_default().insert(info.uid);
+    }
+    naked
+}
+
+fn get_log_config(is_headless: bool, is_daemon: bool) -> log4rs::Config {
     let pattern = PatternEncoder::new(
         "{d(%Y-%m-%d %H:%M:%S)} | {({l}):5.5} | {M}:{L} | {m}{n}",
     );
@@ -1068,7 +1961,21 @@ fn get_log_config(is_headless: bool) -> log4rs::Config {
         .appender(Appender::builder().build("logfile", Box::new(logfile)));
     let mut root = Root::builder();
 
-    if !is_headless {
+    let mut helper_logger = Logger::builder().appender("logfile");
+
+    if is_daemon {
+        // Structured logs for journald & co.
+        let json = ConsoleAppender::builder()
+            .target(Target::Stderr)
+            .encoder(Box::new(JsonEncoder::new()))
+            .build();
+        logger = logger.appender(
+            Appender::builder()
+                .filter(Box::new(ThresholdFilter::new(log::LevelFilter::Info)))
+                .build("json", Box::new(json)),
+        );
+        helper_logger = helper_logger.appender("json");
+    } else if !is_headless {
         logger = logger
             .appender(Appender::builder().build("stderr", Box::new(stderr)));
         root = root.appender("stderr");
@@ -1076,9 +1983,7 @@ fn get_log_config(is_headless: bool) -> log4rs::Config {
 
     logger
         .logger(
-            Logger::builder()
-                .appender("logfile")
-                .build("sf_scrapbook_helper", log::LevelFilter::Debug),
+            helper_logger.build("sf_scrapbook_helper", log::LevelFilter::Debug),
         )
         .logger(
             Logger::builder()
@@ -1,7 +1,10 @@
 use std::{fmt::Write, sync::Arc, time::Duration};
 
 use chrono::Local;
-use config::{CharacterConfig, SFAccCharacter, SFCharIdent};
+use config::{
+    ApiConfig, CharacterConfig, SFAccCharacter, SFCharIdent, TargetFilter,
+    WatchedPlayer,
+};
 use crawler::CrawlerError;
 use iced::Command;
 use log::{error, trace, warn};
@@ -14,13 +17,18 @@ use tokio::time::sleep;
 use ui::OverviewAction;
 
 use self::{
-    backup::{get_newest_backup, restore_backup, RestoreData},
-    login::{SSOIdent, SSOLogin, SSOLoginStatus},
+    backup::{get_newest_backup, restore_backup, RestoreData, ZHofBackup},
+    history::{HistoryEvent, HistoryFilter},
+    login::{SSOIdent, SSOLogin, SSOLoginStatus, SSOStatus},
+    progress::ChartMode,
     ui::underworld::LureTarget,
+    watchlist::WatchAlert,
 };
 use crate::{
+    api::ApiCall,
     crawler::CrawlerState,
-    player::{ScrapbookInfo, UnderworldInfo},
+    player::{total_attributes, ScrapbookInfo, UnderworldInfo},
+    profile::ProfileChoice,
     *,
 };
 
@@ -34,6 +42,12 @@ pub enum Message {
         servers: Option<Vec<String>>,
         concurrency: usize,
     },
+    CLICrawlResumed {
+        url: String,
+        pb: ProgressBar,
+        /// Why the backup could not be used, if it could not be used
+        restored: Result<Box<RestoreData>, String>,
+    },
     NextCLICrawling,
     AdvancedLevelRestrict(bool),
     ShowClasses(bool),
@@ -100,9 +114,22 @@ pub enum Message {
         chars: Vec<Session>,
         provider: SSOProvider,
     },
-    SSORetry,
+    SSOTimedOut(SSOProvider),
     SSOAuthError {
-        _error: String,
+        provider: SSOProvider,
+        error: String,
+    },
+    /// Starts the browser login of the provider (again)
+    SSOStart(SSOProvider),
+    SSOCancel(SSOProvider),
+    /// Retries the failed login of a S&F account
+    SSORetryLogin(SSOIdent),
+    SSODismiss(SSOIdent),
+    /// Removes a saved Google, or Steam account together with the cached
+    /// sessions of its characters
+    SSOForget {
+        name: String,
+        provider: SSOService,
     },
     SetMaxThreads(usize),
     SetStartThreads(usize),
@@ -155,6 +182,12 @@ pub enum Message {
         against: AttackTarget,
         resp: Box<Response>,
     },
+    PlayerTargetVerified {
+        ident: AccountIdent,
+        session: Box<Session>,
+        target: AttackTarget,
+        resp: Box<Response>,
+    },
     PlayerLureResult {
         ident: AccountIdent,
         session: Box<Session>,
@@ -178,7 +211,7 @@ pub enum Message {
         server: ServerID,
         new_count: usize,
     },
-    PageCrawled,
+    PageCrawled(ServerID),
     RemoveAccount {
         ident: AccountIdent,
     },
@@ -195,7 +228,7 @@ pub enum Message {
         ident: AccountIdent,
     },
     CrawlerIdle(ServerID),
-    CrawlerNoPlayerResult,
+    CrawlerNoPlayerResult(ServerID),
     CrawlerUnable {
         server: ServerID,
         action: CrawlAction,
@@ -246,14 +279,121 @@ pub enum Message {
         ident: AccountIdent,
     },
     SetAction(Option<ActionSelection>),
+    HistorySetFilter {
+        ident: AccountIdent,
+        filter: HistoryFilter,
+    },
+    HistorySetSearch {
+        ident: AccountIdent,
+        search: String,
+    },
+    HistoryExport {
+        ident: AccountIdent,
+    },
+    HistoryExportRes {
+        ident: AccountIdent,
+        path: String,
+        error: Option<String>,
+    },
+    ProgressChartMode {
+        ident: AccountIdent,
+        mode: ChartMode,
+    },
+    ItemSelected {
+        ident: AccountIdent,
+        item: EquipmentIdent,
+    },
+    ItemSearch {
+        ident: AccountIdent,
+        search: String,
+    },
+    PlayerSetScoring {
+        ident: AccountIdent,
+        scoring: TargetScoring,
+    },
+    PlayerSetEpicMode {
+        ident: AccountIdent,
+        mode: EpicMode,
+    },
+    /// Changes the text of the script without compiling it
+    PlayerEditScript {
+        ident: AccountIdent,
+        script: String,
+    },
+    /// Compiles & saves the edited script and ranks the targets with it
+    PlayerApplyScript {
+        ident: AccountIdent,
+    },
+    PlayerSetFilter {
+        ident: AccountIdent,
+        underworld: bool,
+        filter: TargetFilter,
+    },
+    /// Changes the draft of the filter without applying it. Used while
+    /// typing, so that half typed text does not change the targets
+    PlayerEditFilter {
+        ident: AccountIdent,
+        underworld: bool,
+        filter: TargetFilter,
+    },
+    TargetSelected {
+        ident: AccountIdent,
+        uid: Option<u32>,
+    },
+    CrawlPlayerNow {
+        server: ServerID,
+        name: String,
+    },
+    PlayerSetBlacklist {
+        ident: AccountIdent,
+        uid: u32,
+        name: String,
+        blacklisted: bool,
+    },
+    WatchlistToggle {
+        server: ServerID,
+        uid: u32,
+        name: String,
+    },
+    ViewWatchlist,
+    WatchlistTick,
+    WatchlistSetInterval(u64),
+    WatchlistDismissAlerts,
+    SoulsDismissAlerts,
+    SetVerifyTargets(bool),
+    SetVerifyWindow(u64),
+    SetLureCooldown(u64),
+    SetSoulsAlert(u8),
+    SetLureRules(LureRules),
+    SetApiEnabled(bool),
+    SetApiPort(u16),
+    SetApiMetrics(bool),
+    ApiNewToken,
+    ApiCall(ApiCall),
+    VaultPasswordChanged(String),
+    VaultUnlock,
+    VaultEnable,
+    VaultDisable,
+    DismissConfigError,
+    ProfileSwitch(ProfileChoice),
+    ProfileNameChanged(String),
+    ProfileCreate,
+    ProfileSaved {
+        name: Option<String>,
+        saved: bool,
+    },
 }
 
 impl Helper {
     pub fn handle_msg(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::UIActive => {}
-            Message::PageCrawled => {
+            Message::PageCrawled(server) => {
                 // Gets handled in crawling
+                self.metrics.crawled(server, "ok");
+                if let Some(command) = self.check_cli_limits(server) {
+                    return command;
+                }
             }
             Message::CrawlerDied { server, error } => {
                 log::error!("Crawler died on {server} - {error}");
@@ -268,6 +408,11 @@ impl Helper {
                 que_id,
                 character,
             } => {
+                self.metrics.crawled(server, "ok");
+                if let Some(command) = self.check_cli_limits(server) {
+                    return command;
+                }
+
                 let Some(server) = self.servers.get_mut(&server) else {
                     return Command::none();
                 };
@@ -296,6 +441,18 @@ impl Helper {
                         let total = remaining + crawled;
                         pb.set_length(total as u64);
                         pb.set_position(crawled as u64);
+                        let json = self
+                            .cli_crawling
+                            .as_ref()
+                            .is_some_and(|a| a.options.json);
+                        if json && crawled % 100 == 0 {
+                            CrawlEvent::Progress {
+                                server: &server.ident.url,
+                                crawled,
+                                remaining,
+                            }
+                            .print();
+                        }
                     };
                     lock.in_flight_accounts.remove(&character.name);
                     lock.todo_pages.is_empty() && lock.todo_accounts.is_empty()
@@ -312,7 +469,27 @@ impl Helper {
 
                 *last_update = Local::now();
 
-                handle_new_char_info(character, equipment, player_info, naked);
+                if self.config.is_watched(server.ident.id, character.uid) {
+                    let alert = WatchAlert::check(
+                        server.ident.id,
+                        player_info.get(&character.uid),
+                        &character,
+                        server.accounts.values(),
+                    );
+                    if let Some(alert) = alert {
+                        info!(
+                            "{} equipped {} missing item(s)",
+                            alert.name,
+                            alert.items.len()
+                        );
+                        self.alerts.push(alert);
+                    }
+                }
+
+                handle_new_char_info(
+                    character, equipment, player_info, naked,
+                    &self.config.lure_rules,
+                );
 
                 if crawler_finished {
                     let mut commands = vec![];
@@ -351,27 +528,27 @@ impl Helper {
                 {
                     return Command::none();
                 }
-                let backup = lock.create_backup(player_info);
-                let ident = server.ident.ident.to_string();
-                let id = server.ident.id;
-
-                return Command::perform(
-                    async move { backup.write(&ident).await },
-                    move |res| Message::BackupRes {
-                        server: id,
-                        error: res.err().map(|a| a.to_string()),
-                    },
-                );
+                drop(lock);
+                return self.write_cli_backup(server_id);
             }
-            Message::CrawlerNoPlayerResult => {
+            Message::CrawlerNoPlayerResult(server) => {
                 // Maybe we want to count this as an error?
                 warn!("No player result");
+                self.metrics.crawled(server, "no_result");
+                if let Some(command) = self.check_cli_limits(server) {
+                    return command;
+                }
             }
             Message::CrawlerUnable {
                 server: server_id,
                 action,
                 error,
             } => {
+                self.metrics.crawled(server_id, error.kind());
+                if let Some(command) = self.check_cli_limits(server_id) {
+                    return command;
+                }
+
                 let Some(server) = self.servers.get_mut(&server_id) else {
                     return Command::none();
                 };
@@ -472,6 +649,7 @@ impl Helper {
                             };
                             sleep(Duration::from_secs(5)).await;
 
+                            session_cache::store(&session_lock);
                             let mut gs = state.gs.lock().unwrap();
                             *gs = new_gs;
                             return;
@@ -501,6 +679,7 @@ impl Helper {
                     pw_hash,
                     self.login_state.remember_me,
                     Default::default(),
+                    false,
                 );
             }
             Message::LoginViewChanged(a) => {
@@ -513,6 +692,7 @@ impl Helper {
                 ident,
             } => {
                 info!("Successfully logged in {ident}",);
+                session_cache::store(&session);
 
                 let Some(server) = self.servers.0.get_mut(&ident.server_id)
                 else {
@@ -558,6 +738,19 @@ impl Helper {
 
                 player.scrapbook_info = ScrapbookInfo::new(&gs, char_conf);
                 player.underworld_info = UnderworldInfo::new(&gs, char_conf);
+                player.history.push(HistoryEvent::Login);
+                if let Some(si) = &player.scrapbook_info {
+                    player.progress.record(si.scrapbook.items.len(), &gs);
+                    player.progress.save_scrapbook(si);
+                }
+                if let Some(underworld) = &gs.underworld {
+                    if player
+                        .economy
+                        .record(underworld, self.config.souls_alert)
+                    {
+                        self.souls_alerts.push(player.name.clone());
+                    }
+                }
 
                 *player.status.lock().unwrap() =
                     AccountStatus::Idle(session, gs);
@@ -565,6 +758,7 @@ impl Helper {
                 let server_ident = server.ident.ident.clone();
                 let server_id = server.ident.id;
                 let afn = self.config.auto_fetch_newest;
+                let rules = self.config.lure_rules.clone();
                 match &server.crawling {
                     CrawlingStatus::Waiting => {
                         server.crawling = CrawlingStatus::Restoring;
@@ -573,7 +767,8 @@ impl Helper {
                                 let backup =
                                     get_newest_backup(server_ident, afn).await;
                                 Box::new(
-                                    restore_backup(backup, total_pages).await,
+                                    restore_backup(backup, total_pages, rules)
+                                        .await,
                                 )
                             },
                             move |backup| Message::ResetCrawling {
@@ -591,9 +786,17 @@ impl Helper {
             }
             Message::LoggininFailure { error, ident } => {
                 error!("Error loggin in {ident}: {error}");
-                let Some((_, player)) = self.servers.get_ident(&ident) else {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(player) = server.accounts.get_mut(&ident.account)
+                else {
                     return Command::none();
                 };
+                player.history.push(HistoryEvent::Error {
+                    message: format!("Login failed: {error}"),
+                });
                 *player.status.lock().unwrap() =
                     AccountStatus::FatalError(error)
             }
@@ -689,6 +892,23 @@ impl Helper {
                     return Command::none();
                 };
                 if let Some(old) = server.accounts.remove(&ident.account) {
+                    // Saved SSO characters need their session to log in
+                    // again. It is removed, once the account is forgotten
+                    let saved = matches!(old.auth, PlayerAuth::SSO)
+                        && self.config.accounts.iter().any(|a| match a {
+                            AccountConfig::SSO { characters, .. } => {
+                                characters.iter().any(|c| {
+                                    c.ident.name.to_lowercase() == old.name
+                                        && ServerIdent::new(&c.ident.server)
+                                            .ident
+                                            == server.ident.ident
+                                })
+                            }
+                            _ => false,
+                        });
+                    if !saved {
+                        session_cache::remove(&server.ident.ident, &old.name);
+                    }
                     if matches!(old.auth, PlayerAuth::SSO) {
                         if let Ok(mut sl) = old.status.lock() {
                             if let Some(session) = sl.take_session("Removing") {
@@ -751,9 +971,10 @@ impl Helper {
                 let tp = (tp as usize).div_ceil(PER_PAGE);
 
                 let id = server.ident.id;
+                let rules = self.config.lure_rules.clone();
 
                 return Command::perform(
-                    async move { Box::new(restore_backup(None, tp).await) },
+                    async move { Box::new(restore_backup(None, tp, rules).await) },
                     move |res| Message::ResetCrawling {
                         server: id,
                         status: res,
@@ -765,6 +986,14 @@ impl Helper {
                 account,
                 auto_login,
             } => match account {
+                AccountConfig::Regular { name, pw_hash, .. }
+                | AccountConfig::SF { name, pw_hash, .. }
+                    if vault::is_reference(&pw_hash) =>
+                {
+                    self.login_state.error = Some(format!(
+                        "The credentials of {name} are locked in the vault"
+                    ));
+                }
                 AccountConfig::Regular {
                     name,
                     pw_hash,
@@ -772,12 +1001,20 @@ impl Helper {
                     ..
                 } => {
                     return self.login_regular(
-                        name, server, pw_hash, false, auto_login,
+                        name, server, pw_hash, false, auto_login, true,
                     );
                 }
                 AccountConfig::SF { name, pw_hash, .. } => {
                     return self.login_sf_acc(name, pw_hash, false, auto_login);
                 }
+                AccountConfig::SSO {
+                    provider,
+                    characters,
+                    ..
+                } => {
+                    return self
+                        .login_sso_acc(provider, characters, auto_login);
+                }
             },
             Message::OrderChange { server, new } => {
                 let Some(server) = self.servers.get_mut(&server) else {
@@ -845,32 +1082,248 @@ impl Helper {
                 };
                 drop(status);
 
-                let tn = target.info.name.clone();
-                let fight = Command::perform(
-                    async move {
-                        let cmd = sf_api::command::Command::Fight {
-                            name: tn,
-                            use_mushroom: false,
-                        };
-                        let resp = session.send_command(&cmd).await;
-                        (resp, session)
-                    },
-                    move |r| match r.0 {
-                        Ok(resp) => Message::PlayerAttackResult {
-                            ident,
-                            session: r.1,
-                            against: target,
-                            resp: Box::new(resp),
+                if self.config.verify_targets
+                    && !target.info.is_fresh(self.config.verify_window)
+                {
+                    let name = target.info.name.clone();
+                    let verify = Command::perform(
+                        async move {
+                            let cmd = sf_api::command::Command::ViewPlayer {
+                                ident: name,
+                            };
+                            let resp = session.send_command(&cmd).await;
+                            (resp, session)
                         },
-                        Err(_) => Message::PlayerCommandFailed {
-                            ident,
-                            session: r.1,
-                            attempt: 0,
+                        move |r| match r.0 {
+                            Ok(resp) => Message::PlayerTargetVerified {
+                                ident,
+                                session: r.1,
+                                target,
+                                resp: Box::new(resp),
+                            },
+                            Err(_) => Message::PlayerCommandFailed {
+                                ident,
+                                session: r.1,
+                                attempt: 0,
+                            },
                         },
-                    },
+                    );
+                    return Command::batch([refetch, verify]);
+                }
+
+                return Command::batch([
+                    refetch,
+                    fight(ident, session, target),
+                ]);
+            }
+            Message::PlayerTargetVerified {
+                ident,
+                session,
+                target,
+                resp,
+            } => {
+                let Some(server) = self.servers.0.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let CrawlingStatus::Crawling {
+                    player_info,
+                    equipment,
+                    naked,
+                    ..
+                } = &mut server.crawling
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+
+                let mut lock = account.status.lock().unwrap();
+                let AccountStatus::Busy(gs, _) = &mut *lock else {
+                    return Command::none();
+                };
+                if let Err(e) = gs.update(*resp) {
+                    account.history.push(HistoryEvent::Error {
+                        message: e.to_string(),
+                    });
+                    *lock = AccountStatus::FatalError(e.to_string());
+                    return Command::none();
+                };
+
+                let Some(si) = &account.scrapbook_info else {
+                    lock.put_session(session);
+                    return Command::none();
+                };
+
+                let Some(player) = gs.lookup.remove_name(&target.info.name)
+                else {
+                    // The player has been deleted, or renamed since we last
+                    // saw them
+                    warn!("Could not verify {}", target.info.name);
+                    lock.put_session(session);
+                    drop(lock);
+                    return self.update_best(ident, false);
+                };
+                let info = CharacterInfo::from_other_player(player);
+                let missing =
+                    info.missing_in(&si.scrapbook.items, si.epic_mode);
+                handle_new_char_info(
+                    info.clone(),
+                    equipment,
+                    player_info,
+                    naked,
+                    &self.config.lure_rules,
                 );
 
-                return Command::batch([refetch, fight]);
+                let score = score_target(si, &info, missing, equipment);
+                // The rarity of the items also changes with every crawled
+                // player, so tiny differences are not worth a re-rank
+                if missing < target.missing || score + 1e-4 < target.score {
+                    // The target changed their equipment, so we have to find
+                    // out, if they are still the best one to attack
+                    debug!(
+                        "{} only scores {score} ({missing} missing) instead \
+                         of {} ({})",
+                        target.info.name, target.score, target.missing
+                    );
+                    lock.put_session(session);
+                    drop(lock);
+                    return self.update_best(ident, false);
+                }
+                drop(lock);
+
+                let target = AttackTarget {
+                    missing,
+                    score,
+                    info,
+                };
+                return fight(ident, session, target);
+            }
+            Message::SetVerifyTargets(nv) => {
+                self.config.verify_targets = nv;
+                _ = self.config.write();
+            }
+            Message::SetVerifyWindow(nv) => {
+                self.config.verify_window = nv;
+                _ = self.config.write();
+            }
+            Message::SetLureCooldown(nv) => {
+                self.config.lure_cooldown = nv;
+                _ = self.config.write();
+            }
+            Message::SetSoulsAlert(nv) => {
+                self.config.souls_alert = nv.min(100);
+                _ = self.config.write();
+            }
+            Message::SetLureRules(rules) => {
+                self.config.lure_rules = rules;
+                _ = self.config.write();
+
+                let mut todo = vec![];
+                for server in self.servers.0.values_mut() {
+                    if let CrawlingStatus::Crawling {
+                        player_info, naked, ..
+                    } = &mut server.crawling
+                    {
+                        *naked =
+                            rebuild_naked(player_info, &self.config.lure_rules);
+                    }
+                    todo.extend(server.accounts.values().map(|a| a.ident));
+                }
+                let mut commands = vec![];
+                for acc in todo {
+                    commands.push(self.update_best(acc, false));
+                }
+                return Command::batch(commands);
+            }
+            Message::SetApiEnabled(nv) => {
+                self.config.api.enabled = nv;
+                if self.config.api.token.is_empty() {
+                    self.config.api.token = ApiConfig::new_token();
+                }
+                _ = self.config.write();
+            }
+            Message::SetApiPort(nv) => {
+                self.config.api.port = nv.max(1);
+                _ = self.config.write();
+            }
+            Message::SetApiMetrics(nv) => {
+                self.config.api.metrics = nv;
+                _ = self.config.write();
+            }
+            Message::ApiNewToken => {
+                self.config.api.token = ApiConfig::new_token();
+                _ = self.config.write();
+            }
+            Message::ApiCall(call) => {
+                return self.handle_api_call(call);
+            }
+            Message::VaultPasswordChanged(nv) => {
+                self.login_state.vault_password = nv;
+            }
+            Message::VaultUnlock => {
+                let password =
+                    std::mem::take(&mut self.login_state.vault_password);
+                if let Err(e) = vault::unlock(&password) {
+                    self.login_state.error = Some(e.to_string());
+                    return Command::none();
+                }
+                self.login_state.error = None;
+                vault::resolve(&mut self.config.accounts);
+                self.current_view = View::Login;
+                return Command::batch(self.auto_login());
+            }
+            Message::VaultEnable => {
+                let password =
+                    std::mem::take(&mut self.login_state.vault_password);
+                if password.is_empty() || vault::is_unlocked() {
+                    return Command::none();
+                }
+                if let Err(e) =
+                    session_cache::change_vault(|| vault::create(&password))
+                {
+                    error!("Could not create the credential vault: {e}");
+                    self.login_state.vault_error =
+                        Some(format!("Could not create the vault: {e}"));
+                    return Command::none();
+                }
+                self.login_state.vault_error = match self.config.write() {
+                    Ok(()) => {
+                        info!("Moved the credentials into the vault");
+                        None
+                    }
+                    Err(e) => Some(format!(
+                        "The vault was created, but the config still \
+                         contains the passwords: {e}"
+                    )),
+                };
+            }
+            Message::VaultDisable => {
+                if self.config.is_locked() {
+                    error!("Can not disable the vault, while it is locked");
+                    self.login_state.vault_error = Some(
+                        "Can not disable the vault, while it is locked".into(),
+                    );
+                    return Command::none();
+                }
+                // The credentials are resolved in memory, so they are written
+                // to the config in plain text again
+                let config = &self.config;
+                let res = session_cache::change_vault(|| {
+                    vault::disable(|| config.write())
+                });
+                self.login_state.vault_error = match res {
+                    Ok(()) => {
+                        info!("Moved the credentials out of the vault");
+                        None
+                    }
+                    Err(e) => {
+                        error!("Could not disable the vault: {e}");
+                        Some(format!("Could not disable the vault: {e}"))
+                    }
+                };
             }
             Message::PlayerCommandFailed {
                 ident,
@@ -889,6 +1342,12 @@ impl Helper {
                 let mut lock = player.status.lock().unwrap();
                 *lock = AccountStatus::LoggingInAgain;
                 drop(lock);
+                if attempt == 0 {
+                    player.history.push(HistoryEvent::Error {
+                        message: "Command failed, logging in again".into(),
+                    });
+                    self.metrics.relogin(ident);
+                }
                 warn!("Logging in {ident} again");
                 return Command::perform(
                     async move {
@@ -937,17 +1396,30 @@ impl Helper {
                     return Command::none();
                 };
 
+                let mushrooms = s.character.mushrooms;
                 if let Err(e) = s.update(*resp) {
+                    account.history.push(HistoryEvent::Error {
+                        message: e.to_string(),
+                    });
                     // it would *probably* be ok to just ignore this in most
                     // cases, but whatever
                     *lock = AccountStatus::FatalError(e.to_string());
                     return Command::none();
                 };
 
+                let spent = mushrooms.saturating_sub(s.character.mushrooms);
+                if spent > 0 {
+                    account
+                        .history
+                        .push(HistoryEvent::Mushrooms { amount: spent });
+                }
+
                 let Some(last) = &s.last_fight else {
                     return Command::none();
                 };
 
+                self.metrics.fight(ident, last.has_player_won);
+
                 let nt = against.info.name.clone();
                 let ut = against.info.uid;
 
@@ -955,12 +1427,31 @@ impl Helper {
                     return Command::none();
                 };
 
+                // The fight may have given us a level up
+                si.level = s.character.level;
+                si.attributes = total_attributes(s);
+
+                let mut items = 0;
                 if last.has_player_won {
                     for new in &against.info.equipment {
-                        si.scrapbook.items.insert(*new);
+                        if si.scrapbook.items.insert(*new) {
+                            items += 1;
+                        }
                     }
                 }
 
+                account.history.push(HistoryEvent::Fight {
+                    target: nt.clone(),
+                    uid: ut,
+                    won: last.has_player_won,
+                    items,
+                });
+
+                if items > 0 {
+                    account.progress.record(si.scrapbook.items.len(), s);
+                    account.progress.save_scrapbook(si);
+                }
+
                 si.attack_log.push((
                     Local::now(),
                     against,
@@ -1083,7 +1574,8 @@ impl Helper {
                 };
                 if remember {
                     self.config.accounts.retain(|a| match &a {
-                        AccountConfig::Regular { .. } => true,
+                        AccountConfig::Regular { .. }
+                        | AccountConfig::SSO { .. } => true,
                         AccountConfig::SF { name: uuu, .. } => {
                             name.to_lowercase() != uuu.to_lowercase()
                         }
@@ -1189,7 +1681,13 @@ impl Helper {
             Message::SSOImport { pos } => {
                 // TODO: Bounds check this?
                 let account = self.login_state.import_que.remove(pos);
-                return self.login(account, false, PlayerAuth::SSO, false);
+                return self.login(
+                    account,
+                    false,
+                    PlayerAuth::SSO,
+                    false,
+                    true,
+                );
             }
             Message::ViewSubPage { player, page } => {
                 self.current_view = View::Account {
@@ -1232,9 +1730,14 @@ impl Helper {
                 let new_sso = SSOLogin {
                     ident,
                     status: SSOLoginStatus::Success,
+                    pw_hash: None,
+                    remember: self.login_state.remember_me,
                 };
 
                 self.login_state.active_sso.push(new_sso);
+                if self.login_state.remember_me {
+                    self.remember_sso(auth_name, provider.into(), &chars);
+                }
                 self.login_state.import_que.append(&mut chars);
 
                 if self.current_view == View::Login
@@ -1244,11 +1747,68 @@ impl Helper {
                     self.login_state.login_typ = LoginType::SSOChars;
                 };
             }
-            Message::SSORetry => {
-                // The subscription will handle this
+            Message::SSOTimedOut(provider) => {
+                info!("The {provider:?} login timed out");
+            }
+            Message::SSOAuthError { provider, error } => {
+                warn!("The {provider:?} login failed: {error}");
+            }
+            Message::SSOStart(provider) => {
+                let status = self.login_state.sso_status(provider);
+                let mut status = status.lock().unwrap();
+                if !status.is_active() {
+                    *status = SSOStatus::Initializing;
+                }
+            }
+            Message::SSOCancel(provider) => {
+                // The subscription is dropped, once the login is no longer
+                // active
+                let status = self.login_state.sso_status(provider);
+                *status.lock().unwrap() = SSOStatus::Cancelled;
             }
-            Message::SSOAuthError { .. } => {
-                // TODO: Display this I guess
+            Message::SSORetryLogin(ident) => {
+                let Some(pos) = self
+                    .login_state
+                    .active_sso
+                    .iter()
+                    .position(|a| a.ident == ident)
+                else {
+                    return Command::none();
+                };
+                let SSOIdent::SF(name) = ident else {
+                    return Command::none();
+                };
+                let Some(pw_hash) =
+                    self.login_state.active_sso[pos].pw_hash.clone()
+                else {
+                    return Command::none();
+                };
+                // The retry should behave like the original login, even if
+                // the checkbox changed in the meantime
+                let old = self.login_state.active_sso.remove(pos);
+                return self.login_sf_acc(name, pw_hash, old.remember, false);
+            }
+            Message::SSODismiss(ident) => {
+                self.login_state.active_sso.retain(|a| a.ident != ident);
+            }
+            Message::SSOForget { name, provider } => {
+                self.config.accounts.retain(|a| match a {
+                    AccountConfig::SSO {
+                        name: s_name,
+                        provider: s_provider,
+                        characters,
+                    } if *s_name == name && *s_provider == provider => {
+                        for char in characters {
+                            let server = ServerIdent::new(&char.ident.server);
+                            session_cache::remove(
+                                &server.ident, &char.ident.name,
+                            );
+                        }
+                        false
+                    }
+                    _ => true,
+                });
+                _ = self.config.write();
             }
             Message::OpenLink(url) => {
                 _ = open::that(url);
@@ -1277,35 +1837,11 @@ impl Helper {
                     return Command::none();
                 }
 
-                let Some(mut session) = status.take_session("Fighting") else {
+                let Some(session) = status.take_session("Fighting") else {
                     return Command::none();
                 };
                 drop(status);
-                let ident = account.ident;
-                let tn = target.info.name.clone();
-                return Command::perform(
-                    async move {
-                        let cmd = sf_api::command::Command::Fight {
-                            name: tn,
-                            use_mushroom: false,
-                        };
-                        let resp = session.send_command(&cmd).await;
-                        (resp, session)
-                    },
-                    move |r| match r.0 {
-                        Ok(resp) => Message::PlayerAttackResult {
-                            ident,
-                            session: r.1,
-                            against: target,
-                            resp: Box::new(resp),
-                        },
-                        Err(_) => Message::PlayerCommandFailed {
-                            ident,
-                            session: r.1,
-                            attempt: 0,
-                        },
-                    },
-                );
+                return fight(account.ident, session, target);
             }
             Message::PlayerSetMaxLvl { ident, max } => {
                 let Some(server) = self.servers.get_mut(&ident.server_id)
@@ -1374,6 +1910,13 @@ impl Helper {
                 let Some(pb) = server.headless_progress.clone() else {
                     return Command::none();
                 };
+                if self.cli_crawling.as_ref().is_some_and(|a| a.options.json) {
+                    CrawlEvent::Finished {
+                        server: &server.ident.url,
+                        error: error.as_deref(),
+                    }
+                    .print();
+                }
                 if let Some(err) = error {
                     pb.println(err)
                 }
@@ -1403,54 +1946,24 @@ impl Helper {
                     return Command::none();
                 };
 
-                let mut best = si.best.first().cloned();
-                let mut scrapbook = si.scrapbook.items.clone();
-
-                let mut per_player_counts = calc_per_player_count(
-                    player_info, equipment, &scrapbook, si,
-                    self.config.blacklist_threshold,
-                );
-
-                let mut target_list = Vec::new();
-                let mut loop_count = 0;
                 let lock = que.lock().unwrap();
                 let invalid =
                     lock.invalid_accounts.iter().map(|a| a.as_str()).collect();
 
-                while let Some(AttackTarget { missing, info }) = best {
-                    if loop_count > 300 || missing == 0 {
-                        break;
-                    }
-                    loop_count += 1;
-
-                    for eq in &info.equipment {
-                        if scrapbook.contains(eq) {
-                            continue;
-                        }
-                        let Some(players) = equipment.get(eq) else {
-                            continue;
-                        };
-                        // We decrease the new equipment count of all players,
-                        // that have the same item as
-                        // the one we just "found"
-                        for player in players {
-                            let ppc =
-                                per_player_counts.entry(*player).or_insert(1);
-                            *ppc = ppc.saturating_sub(1);
-                        }
-                    }
-
-                    scrapbook.extend(info.equipment);
-                    target_list.push(info.name);
-                    let best_players =
-                        find_best(&per_player_counts, player_info, 1, &invalid);
-                    best = best_players.into_iter().next();
-                }
+                let target_list = calc_battle_order(
+                    si.best.first().cloned(),
+                    player_info,
+                    equipment,
+                    si,
+                    &invalid,
+                    self.config.blacklist_threshold,
+                );
                 drop(lock);
                 return iced::clipboard::write(target_list.join("/"));
             }
             Message::PlayerRelogSuccess { ident, gs, session } => {
                 info!("Relogin success");
+                session_cache::store(&session);
                 let Some(server) = self.servers.0.get_mut(&ident.server_id)
                 else {
                     return Command::none();
@@ -1463,6 +1976,7 @@ impl Helper {
                 let mut lock = player.status.lock().unwrap();
                 *lock = AccountStatus::Busy(gs, "Waiting".into());
                 drop(lock);
+                player.history.push(HistoryEvent::Relogin);
                 // For some reason the game does not like sending requests
                 // immediately
                 return Command::perform(
@@ -1473,12 +1987,19 @@ impl Helper {
                 );
             }
             Message::SSOLoginFailure { name, error } => {
-                self
+                warn!("Could not log in the S&F account {name}: {error}");
+                let ident = SSOIdent::SF(name);
+                // The error is kept with the account, so that it can be
+                // retried from the list of SSO accounts
+                if let Some(active) = self
                     .login_state
                     .active_sso
-                    .retain(|a| !matches!(&a.ident, SSOIdent::SF(s) if s.as_str() == name.as_str()));
-                self.login_state.login_typ = LoginType::SFAccount;
-                self.login_state.error = Some(error)
+                    .iter_mut()
+                    .find(|a| a.ident == ident)
+                {
+                    active.status = SSOLoginStatus::Error(error);
+                }
+                self.login_state.login_typ = LoginType::SSOAccounts;
             }
             Message::PlayerLure { ident, target } => {
                 let Some(server) = self.servers.get_mut(&ident.server_id)
@@ -1552,7 +2073,11 @@ impl Helper {
                     return Command::none();
                 };
 
+                let silver = s.character.silver;
                 if let Err(e) = s.update(*resp) {
+                    account.history.push(HistoryEvent::Error {
+                        message: e.to_string(),
+                    });
                     // it would *probably* be ok to just ignore this in most
                     // cases, but whatever
                     *lock = AccountStatus::FatalError(e.to_string());
@@ -1563,10 +2088,46 @@ impl Helper {
                     return Command::none();
                 };
 
+                self.metrics.lure(ident, last.has_player_won);
+
+                if last.has_player_won {
+                    account
+                        .economy
+                        .record_lure(s.character.silver.saturating_sub(silver));
+                }
+                if let Some(underworld) = &s.underworld {
+                    if account
+                        .economy
+                        .record(underworld, self.config.souls_alert)
+                    {
+                        self.souls_alerts.push(account.name.clone());
+                    }
+                }
+
                 let Some(si) = &mut account.underworld_info else {
                     return Command::none();
                 };
 
+                account.history.push(HistoryEvent::Lure {
+                    target: against.name.clone(),
+                    uid: against.uid,
+                    won: last.has_player_won,
+                });
+                if let Some(stats) = &mut account.lure_stats {
+                    let class = match &server.crawling {
+                        CrawlingStatus::Crawling { player_info, .. } => {
+                            player_info.get(&against.uid).and_then(|a| a.class)
+                        }
+                        _ => None,
+                    };
+                    stats.record(
+                        against.uid,
+                        class,
+                        last.has_player_won,
+                        Local::now(),
+                    );
+                }
+
                 si.attack_log.push((
                     Local::now(),
                     against.name,
@@ -1577,6 +2138,8 @@ impl Helper {
                     si.underworld = underworld.clone();
                 }
                 lock.put_session(session);
+                drop(lock);
+                return self.update_best(ident, false);
             }
             Message::PlayerNotPolled { ident } => {
                 warn!("Unable to update {ident}")
@@ -1605,6 +2168,19 @@ impl Helper {
                         sbi.underworld = sb.clone();
                     }
                 }
+                if let Some(underworld) = &gs.underworld {
+                    if account
+                        .economy
+                        .record(underworld, self.config.souls_alert)
+                    {
+                        self.souls_alerts.push(account.name.clone());
+                    }
+                }
+
+                if let Some(si) = &account.scrapbook_info {
+                    account.progress.record(si.scrapbook.items.len(), gs);
+                    account.progress.save_scrapbook(si);
+                }
 
                 drop(lock);
             }
@@ -1640,32 +2216,11 @@ impl Helper {
                 };
                 if let CrawlingStatus::Crawling { que, .. } = &server.crawling {
                     let mut que = que.lock().unwrap();
-                    que.min_level = min.max(1);
-                    que.max_level = max.max(min).min(9999);
-
+                    que.set_level_range(min, max);
                     debug!(
                         "Changed MinMax to {}/{}",
                         que.min_level, que.max_level
                     );
-                    let mut to_remove = IntSet::default();
-                    for (lvl, _) in
-                        que.lvl_skipped_accounts.range(0..que.min_level)
-                    {
-                        to_remove.insert(*lvl);
-                    }
-                    for (lvl, _) in
-                        que.lvl_skipped_accounts.range(que.max_level + 1..)
-                    {
-                        to_remove.insert(*lvl);
-                    }
-                    for lvl in to_remove {
-                        let Some(mut todo) =
-                            que.lvl_skipped_accounts.remove(&lvl)
-                        else {
-                            continue;
-                        };
-                        que.todo_accounts.append(&mut todo);
-                    }
                 }
             }
             Message::ShowClasses(val) => {
@@ -1676,32 +2231,86 @@ impl Helper {
                 let Some(cli) = &mut self.cli_crawling else {
                     return Command::none();
                 };
-                let pb = cli.mbp.add(ProgressBar::new_spinner());
+                let pb = if cli.options.json {
+                    ProgressBar::hidden()
+                } else {
+                    cli.mbp.add(ProgressBar::new_spinner())
+                };
 
                 let Some(url) = cli.todo_servers.pop() else {
                     cli.active -= 1;
                     if cli.active == 0 {
                         pb.println("Finished Crawling all servers");
                         pb.finish_and_clear();
+                        if cli.options.json {
+                            CrawlEvent::Done.print();
+                        }
                         std::process::exit(0);
                     }
                     pb.finish_and_clear();
                     return Command::none();
                 };
-                let threads = cli.threads;
-                return match self.force_init_crawling(&url, threads, pb.clone())
-                {
-                    Some(s) => s,
-                    None => {
-                        pb.println(format!(
-                            "Could not init crawling on: {url}"
-                        ));
-                        pb.finish_and_clear();
-                        return Command::perform(async {}, |_| {
-                            Message::NextCLICrawling
-                        });
+
+                if cli.options.resume {
+                    let ident = ServerIdent::new(&url).ident;
+                    let rules = self.config.lure_rules.clone();
+                    return Command::perform(
+                        async move {
+                            let backup = match ZHofBackup::read(&ident).await {
+                                Ok(backup) => backup,
+                                Err(e)
+                                    if e.kind()
+                                        == std::io::ErrorKind::NotFound =>
+                                {
+                                    return Err("No backup found".to_string());
+                                }
+                                Err(e) => {
+                                    return Err(format!(
+                                        "Could not read the backup: {e}"
+                                    ));
+                                }
+                            };
+                            let restored = restore_backup(
+                                Some(Box::new(backup)),
+                                0,
+                                rules,
+                            )
+                            .await;
+                            Ok(Box::new(restored))
+                        },
+                        move |restored| Message::CLICrawlResumed {
+                            url,
+                            pb,
+                            restored,
+                        },
+                    );
+                }
+                return self.start_cli_crawling(url, pb, None);
+            }
+            Message::CLICrawlResumed { url, pb, restored } => {
+                let restored = match restored {
+                    Ok(restored) => Some(restored),
+                    Err(error) => {
+                        warn!("Can not resume {url}: {error}");
+                        let json = self
+                            .cli_crawling
+                            .as_ref()
+                            .is_some_and(|a| a.options.json);
+                        if json {
+                            CrawlEvent::ResumeFailed {
+                                server: &url,
+                                error: &error,
+                            }
+                            .print();
+                        } else {
+                            pb.println(format!(
+                                "{url}: {error}. Starting a fresh crawl"
+                            ));
+                        }
+                        None
                     }
                 };
+                return self.start_cli_crawling(url, pb, restored);
             }
             Message::CrawlAllRes {
                 servers,
@@ -1714,7 +2323,10 @@ impl Helper {
                     _ = cli.mbp.println("Could not fetch server list");
                     std::process::exit(1);
                 };
-                cli.todo_servers = servers;
+                cli.todo_servers = servers
+                    .into_iter()
+                    .filter(|a| cli.options.should_crawl(a))
+                    .collect();
                 let mut res = vec![];
                 for _ in 0..concurrency {
                     res.push(Command::perform(async {}, |_| {
@@ -1751,7 +2363,7 @@ impl Helper {
                     return Command::none();
                 };
                 let account = self.login_state.import_que.remove(pos);
-                return self.login(account, false, PlayerAuth::SSO, true);
+                return self.login(account, false, PlayerAuth::SSO, true, true);
             }
             Message::SetOverviewSelected { ident, val } => {
                 let View::Overview { selected, action } =
@@ -1932,6 +2544,411 @@ impl Helper {
 
                 return iced::clipboard::write(res);
             }
+            Message::HistorySetFilter { ident, filter } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                account.history.filter = filter;
+            }
+            Message::HistorySetSearch { ident, search } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                account.history.search = search;
+            }
+            Message::HistoryExport { ident } => {
+                let Some((_, account)) = self.servers.get_ident(&ident) else {
+                    return Command::none();
+                };
+                let csv = account.history.to_csv();
+                let path = account.history.csv_path();
+                return Command::perform(
+                    async move {
+                        let res = tokio::fs::write(&path, csv).await;
+                        (path, res)
+                    },
+                    move |(path, res)| Message::HistoryExportRes {
+                        ident,
+                        path: path.to_string_lossy().to_string(),
+                        error: res.err().map(|a| a.to_string()),
+                    },
+                );
+            }
+            Message::HistoryExportRes { ident, path, error } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                account.history.export_status = Some(match error {
+                    Some(error) => format!("Export failed: {error}"),
+                    None => format!("Exported to {path}"),
+                });
+            }
+            Message::ProgressChartMode { ident, mode } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                account.progress.chart_mode = mode;
+            }
+            Message::ItemSelected { ident, item } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                let Some(si) = &mut account.scrapbook_info else {
+                    return Command::none();
+                };
+                si.selected_item = Some(item);
+            }
+            Message::ItemSearch { ident, search } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                let Some(si) = &mut account.scrapbook_info else {
+                    return Command::none();
+                };
+                si.item_search = search;
+            }
+            Message::PlayerSetScoring { ident, scoring } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                let Some(si) = &mut account.scrapbook_info else {
+                    return Command::none();
+                };
+                si.scoring = scoring;
+                if let Some(config) = self
+                    .config
+                    .get_char_conf_mut(&account.name, ident.server_id)
+                {
+                    config.scoring = scoring;
+                    _ = self.config.write();
+                }
+                return self.update_best(ident, false);
+            }
+            Message::PlayerSetEpicMode { ident, mode } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                let Some(si) = &mut account.scrapbook_info else {
+                    return Command::none();
+                };
+                si.epic_mode = mode;
+                if let Some(config) = self
+                    .config
+                    .get_char_conf_mut(&account.name, ident.server_id)
+                {
+                    config.epic_mode = mode;
+                    _ = self.config.write();
+                }
+                return self.update_best(ident, false);
+            }
+            Message::PlayerEditScript { ident, script } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                if let Some(si) = &mut account.scrapbook_info {
+                    si.score_script = script;
+                }
+            }
+            Message::PlayerApplyScript { ident } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                let Some(si) = &mut account.scrapbook_info else {
+                    return Command::none();
+                };
+                let script = si.score_script.clone();
+                match ScoreScript::compile(&script) {
+                    Ok(compiled) => {
+                        si.script = Some(compiled);
+                        si.script_error = None;
+                    }
+                    Err(e) => {
+                        si.script = None;
+                        si.script_error = Some(e);
+                    }
+                }
+                if let Some(config) = self
+                    .config
+                    .get_char_conf_mut(&account.name, ident.server_id)
+                {
+                    config.score_script = script;
+                    _ = self.config.write();
+                }
+                return self.update_best(ident, false);
+            }
+            Message::TargetSelected { ident, uid } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                let Some(si) = &mut account.scrapbook_info else {
+                    return Command::none();
+                };
+                si.selected_target = uid;
+            }
+            Message::CrawlPlayerNow { server, name } => {
+                let Some(server) = self.servers.get_mut(&server) else {
+                    return Command::none();
+                };
+                let CrawlingStatus::Crawling { que, threads, .. } =
+                    &server.crawling
+                else {
+                    return Command::none();
+                };
+                let mut lock = que.lock().unwrap();
+                if !lock.in_flight_accounts.contains(&name) {
+                    // The crawler takes accounts from the back, so this will
+                    // be the next player fetched
+                    lock.todo_accounts.retain(|a| a != &name);
+                    lock.invalid_accounts.retain(|a| a != &name);
+                    lock.todo_accounts.push(name);
+                }
+                drop(lock);
+                if *threads == 0 {
+                    return server.set_threads(1, &self.config.base_name);
+                }
+            }
+            Message::PlayerSetBlacklist {
+                ident,
+                uid,
+                name,
+                blacklisted,
+            } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                let Some(si) = &mut account.scrapbook_info else {
+                    return Command::none();
+                };
+                match blacklisted {
+                    true => {
+                        let th = self.config.blacklist_threshold.max(1);
+                        let entry =
+                            si.blacklist.entry(uid).or_insert((name, 0));
+                        entry.1 = entry.1.max(th);
+                    }
+                    false => {
+                        si.blacklist.remove(&uid);
+                    }
+                }
+                return self.update_best(ident, false);
+            }
+            Message::WatchlistToggle { server, uid, name } => {
+                let Some(server) = self.servers.get(&server) else {
+                    return Command::none();
+                };
+                let server_id = server.ident.id;
+                if self.config.is_watched(server_id, uid) {
+                    self.config.watchlist.retain(|a| {
+                        a.uid != uid
+                            || ServerIdent::new(&a.server).id != server_id
+                    });
+                } else {
+                    self.config.watchlist.push(WatchedPlayer {
+                        server: server.ident.url.clone(),
+                        uid,
+                        name,
+                    });
+                }
+                _ = self.config.write();
+            }
+            Message::ViewWatchlist => {
+                self.current_view = View::Watchlist;
+            }
+            Message::WatchlistTick => {
+                let mut commands = vec![];
+                for watched in &self.config.watchlist {
+                    let server_id = ServerIdent::new(&watched.server).id;
+                    let Some(server) = self.servers.get_mut(&server_id) else {
+                        continue;
+                    };
+                    let CrawlingStatus::Crawling { que, threads, .. } =
+                        &server.crawling
+                    else {
+                        continue;
+                    };
+                    let mut lock = que.lock().unwrap();
+                    if lock.todo_accounts.contains(&watched.name)
+                        || lock.in_flight_accounts.contains(&watched.name)
+                    {
+                        continue;
+                    }
+                    trace!("Re-crawling watched player {}", watched.name);
+                    lock.todo_accounts.push(watched.name.clone());
+                    drop(lock);
+                    if *threads == 0 {
+                        commands.push(
+                            server.set_threads(1, &self.config.base_name),
+                        );
+                    }
+                }
+                return Command::batch(commands);
+            }
+            Message::WatchlistSetInterval(nv) => {
+                self.config.watch_interval = nv.max(1);
+                _ = self.config.write();
+            }
+            Message::WatchlistDismissAlerts => {
+                self.alerts.clear();
+            }
+            Message::DismissConfigError => {
+                if let Some(issue) = self.config_error.take() {
+                    // The broken config has been backed up, so the current
+                    // one may replace it from now on
+                    if issue.recoverable {
+                        Config::block_writes(false);
+                    }
+                }
+            }
+            Message::ProfileSwitch(choice) => {
+                if choice.0.as_deref() != profile::active() {
+                    return self.switch_profile(choice.0);
+                }
+            }
+            Message::ProfileNameChanged(nv) => {
+                self.new_profile = nv;
+            }
+            Message::ProfileCreate => {
+                if profile::is_valid_name(&self.new_profile) {
+                    return self.switch_profile(Some(self.new_profile.clone()));
+                }
+            }
+            Message::ProfileSaved { name, saved } => {
+                if !saved {
+                    error!("Not switching the profile, to not lose progress");
+                    return Command::none();
+                }
+                profile::switch(name.as_deref());
+            }
+            Message::SoulsDismissAlerts => {
+                self.souls_alerts.clear();
+            }
+            Message::PlayerEditFilter {
+                ident,
+                underworld,
+                filter,
+            } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                match underworld {
+                    true => {
+                        if let Some(ui) = &mut account.underworld_info {
+                            ui.filter_draft = filter;
+                        }
+                    }
+                    false => {
+                        if let Some(si) = &mut account.scrapbook_info {
+                            si.filter_draft = filter;
+                        }
+                    }
+                }
+            }
+            Message::PlayerSetFilter {
+                ident,
+                underworld,
+                filter,
+            } => {
+                let Some(server) = self.servers.get_mut(&ident.server_id)
+                else {
+                    return Command::none();
+                };
+                let Some(account) = server.accounts.get_mut(&ident.account)
+                else {
+                    return Command::none();
+                };
+                match underworld {
+                    true => {
+                        let Some(ui) = &mut account.underworld_info else {
+                            return Command::none();
+                        };
+                        ui.filter = filter.clone();
+                        ui.filter_draft = filter.clone();
+                    }
+                    false => {
+                        let Some(si) = &mut account.scrapbook_info else {
+                            return Command::none();
+                        };
+                        si.filter = filter.clone();
+                        si.filter_draft = filter.clone();
+                    }
+                }
+                if let Some(config) = self
+                    .config
+                    .get_char_conf_mut(&account.name, ident.server_id)
+                {
+                    match underworld {
+                        true => config.underworld_filter = filter,
+                        false => config.scrapbook_filter = filter,
+                    }
+                    _ = self.config.write();
+                }
+                return self.update_best(ident, false);
+            }
             Message::SetAction(a) => {
                 let View::Overview { action, .. } = &mut self.current_view
                 else {
@@ -1976,3 +2993,34 @@ impl Helper {
         Command::none()
     }
 }
+
+fn fight(
+    ident: AccountIdent,
+    mut session: Box<Session>,
+    target: AttackTarget,
+) -> Command<Message> {
+    let tn = target.info.name.clone();
+    Command::perform(
+        async move {
+            let cmd = sf_api::command::Command::Fight {
+                name: tn,
+                use_mushroom: false,
+            };
+            let resp = session.send_command(&cmd).await;
+            (resp, session)
+        },
+        move |r| match r.0 {
+            Ok(resp) => Message::PlayerAttackResult {
+                ident,
+                session: r.1,
+                against: target,
+                resp: Box::new(resp),
+            },
+            Err(_) => Message::PlayerCommandFailed {
+                ident,
+                session: r.1,
+                attempt: 0,
+            },
+        },
+    )
+}
@@ -0,0 +1,213 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{
+    player::AccountStatus,
+    server::{CrawlingStatus, Servers},
+    AccountIdent, ServerID,
+};
+
+/// Counters, that can not be derived from the current state. Everything else
+/// is read directly from the servers, once the metrics are requested
+#[derive(Debug, Default)]
+pub struct Metrics {
+    crawler_requests: HashMap<(ServerID, &'static str), u64>,
+    fights: HashMap<(AccountIdent, bool), u64>,
+    lures: HashMap<(AccountIdent, bool), u64>,
+    relogins: HashMap<AccountIdent, u64>,
+}
+
+impl Metrics {
+    pub fn crawled(&mut self, server: ServerID, outcome: &'static str) {
+        *self.crawler_requests.entry((server, outcome)).or_default() += 1;
+    }
+
+    /// The total amount of requests the crawler made on this server
+    pub fn crawler_requests(&self, server: ServerID) -> u64 {
+        self.crawler_requests
+            .iter()
+            .filter(|((id, _), _)| *id == server)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    pub fn fight(&mut self, ident: AccountIdent, won: bool) {
+        *self.fights.entry((ident, won)).or_default() += 1;
+    }
+
+    pub fn lure(&mut self, ident: AccountIdent, won: bool) {
+        *self.lures.entry((ident, won)).or_default() += 1;
+    }
+
+    pub fn relogin(&mut self, ident: AccountIdent) {
+        *self.relogins.entry(ident).or_default() += 1;
+    }
+
+    /// Renders all metrics in the prometheus text format
+    pub fn render(&self, servers: &Servers) -> String {
+        let mut out = String::new();
+        let server_label = |id: &ServerID| {
+            servers
+                .get(id)
+                .map(|a| format!("server=\"{}\"", escape(&a.ident.url)))
+        };
+        let account_label = |ident: &AccountIdent| {
+            let server = servers.get(&ident.server_id)?;
+            let account = server.accounts.get(&ident.account)?;
+            Some(format!(
+                "server=\"{}\",account=\"{}\"",
+                escape(&server.ident.url),
+                escape(&account.name)
+            ))
+        };
+        let outcome = |won: bool| if won { "won" } else { "lost" };
+
+        header(
+            &mut out, "sf_crawler_requests_total", "counter",
+            "Crawler requests by outcome or error kind",
+        );
+        for ((id, res), count) in &self.crawler_requests {
+            let Some(labels) = server_label(id) else {
+                continue;
+            };
+            _ = writeln!(
+                out,
+                "sf_crawler_requests_total{{{labels},outcome=\"{res}\"}} \
+                 {count}"
+            );
+        }
+
+        header(&mut out, "sf_fights_total", "counter", "Fights by outcome");
+        for ((ident, won), count) in &self.fights {
+            let Some(labels) = account_label(ident) else {
+                continue;
+            };
+            let res = outcome(*won);
+            _ = writeln!(
+                out,
+                "sf_fights_total{{{labels},outcome=\"{res}\"}} {count}"
+            );
+        }
+
+        header(&mut out, "sf_lures_total", "counter", "Lures by outcome");
+        for ((ident, won), count) in &self.lures {
+            let Some(labels) = account_label(ident) else {
+                continue;
+            };
+            let res = outcome(*won);
+            _ = writeln!(
+                out,
+                "sf_lures_total{{{labels},outcome=\"{res}\"}} {count}"
+            );
+        }
+
+        header(
+            &mut out, "sf_relogins_total", "counter",
+            "Relogins after a failed command",
+        );
+        for (ident, count) in &self.relogins {
+            let Some(labels) = account_label(ident) else {
+                continue;
+            };
+            _ = writeln!(out, "sf_relogins_total{{{labels}}} {count}");
+        }
+
+        header(
+            &mut out, "sf_account_session_state", "gauge",
+            "The current session state of an account",
+        );
+        for server in servers.0.values() {
+            for account in server.accounts.values() {
+                let Some(labels) = account_label(&account.ident) else {
+                    continue;
+                };
+                let current = match &*account.status.lock().unwrap() {
+                    AccountStatus::LoggingIn => "logging_in",
+                    AccountStatus::Idle(..) => "idle",
+                    AccountStatus::Busy(..) => "busy",
+                    AccountStatus::FatalError(_) => "fatal_error",
+                    AccountStatus::LoggingInAgain => "logging_in_again",
+                };
+                for state in [
+                    "logging_in", "idle", "busy", "fatal_error",
+                    "logging_in_again",
+                ] {
+                    let value = u8::from(state == current);
+                    _ = writeln!(
+                        out,
+                        "sf_account_session_state{{{labels},state=\"{state}\"}} \
+                         {value}"
+                    );
+                }
+            }
+        }
+
+        header(
+            &mut out, "sf_players_indexed", "gauge",
+            "Players in the hall of fame index of a server",
+        );
+        let mut queues = String::new();
+        let mut threads = String::new();
+        for server in servers.0.values() {
+            let CrawlingStatus::Crawling {
+                threads: thread_count,
+                que,
+                player_info,
+                ..
+            } = &server.crawling
+            else {
+                continue;
+            };
+            let Some(labels) = server_label(&server.ident.id) else {
+                continue;
+            };
+            _ = writeln!(
+                out,
+                "sf_players_indexed{{{labels}}} {}",
+                player_info.len()
+            );
+            _ = writeln!(
+                threads,
+                "sf_crawler_threads{{{labels}}} {thread_count}"
+            );
+
+            let que = que.lock().unwrap();
+            for (name, len) in [
+                ("todo_pages", que.todo_pages.len()),
+                ("todo_accounts", que.todo_accounts.len()),
+                ("in_flight_pages", que.in_flight_pages.len()),
+                ("in_flight_accounts", que.in_flight_accounts.len()),
+                ("invalid_pages", que.invalid_pages.len()),
+                ("invalid_accounts", que.invalid_accounts.len()),
+            ] {
+                _ = writeln!(
+                    queues,
+                    "sf_crawler_queue{{{labels},queue=\"{name}\"}} {len}"
+                );
+            }
+        }
+
+        header(
+            &mut out, "sf_crawler_queue", "gauge",
+            "The amount of entries in the crawler queues",
+        );
+        out.push_str(&queues);
+        header(
+            &mut out, "sf_crawler_threads", "gauge",
+            "The amount of active crawler threads",
+        );
+        out.push_str(&threads);
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, typ: &str, help: &str) {
+    _ = writeln!(out, "# HELP {name} {help}");
+    _ = writeln!(out, "# TYPE {name} {typ}");
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
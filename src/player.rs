@@ -7,14 +7,25 @@ use chrono::{DateTime, Local};
 use log::trace;
 use nohash_hasher::IntMap;
 use sf_api::{
-    gamestate::{underworld::Underworld, unlockables::ScrapBook, GameState},
+    gamestate::{
+        underworld::Underworld,
+        unlockables::{EquipmentIdent, ScrapBook},
+        GameState,
+    },
     session::Session,
 };
 use tokio::time::sleep;
 
 use crate::{
-    config::CharacterConfig, login::PlayerAuth, message::Message, AccountIdent,
-    AttackTarget, CharacterInfo,
+    config::{CharacterConfig, EpicMode, TargetFilter, TargetScoring},
+    economy::UnderworldEconomy,
+    history::AccountHistory,
+    login::PlayerAuth,
+    lure::{avg_unit_level, LureStats},
+    message::Message,
+    progress::ScrapbookProgress,
+    scripting::ScoreScript,
+    AccountIdent, AttackTarget, CharacterInfo,
 };
 
 pub struct AccountInfo {
@@ -25,6 +36,12 @@ pub struct AccountInfo {
     pub status: Arc<Mutex<AccountStatus>>,
     pub scrapbook_info: Option<ScrapbookInfo>,
     pub underworld_info: Option<UnderworldInfo>,
+    pub history: AccountHistory,
+    /// The outcomes of past lures. Built from the history, once the crawler
+    /// knows the classes of the targets
+    pub lure_stats: Option<LureStats>,
+    pub progress: ScrapbookProgress,
+    pub economy: UnderworldEconomy,
 }
 
 pub struct UnderworldInfo {
@@ -33,6 +50,13 @@ pub struct UnderworldInfo {
     pub max_level: u16,
     pub attack_log: Vec<(DateTime<Local>, String, bool)>,
     pub auto_lure: bool,
+    pub filter: TargetFilter,
+    /// The filter shown in the UI. Text is typed into this and only copied
+    /// into `filter`, once it is applied
+    pub filter_draft: TargetFilter,
+    /// The predicted chance to successfully lure each of the players in
+    /// `best`
+    pub chances: IntMap<u32, f32>,
 }
 
 impl UnderworldInfo {
@@ -41,19 +65,19 @@ impl UnderworldInfo {
         config: Option<&CharacterConfig>,
     ) -> Option<Self> {
         let underworld = gs.underworld.as_ref()?.clone();
-        let avg_lvl = underworld
-            .units
-            .as_array()
-            .iter()
-            .map(|a| a.level as u64)
-            .sum::<u64>() as f32
-            / 3.0;
+        let avg_lvl = avg_unit_level(&underworld);
+        let filter = config
+            .map(|a| a.underworld_filter.clone())
+            .unwrap_or_default();
         Some(Self {
             underworld,
             best: Default::default(),
             max_level: avg_lvl as u16 + 20,
             attack_log: Vec::new(),
             auto_lure: config.map(|a| a.auto_lure).unwrap_or(false),
+            filter_draft: filter.clone(),
+            filter,
+            chances: Default::default(),
         })
     }
 }
@@ -61,11 +85,42 @@ impl UnderworldInfo {
 pub struct ScrapbookInfo {
     pub scrapbook: ScrapBook,
     pub best: Vec<AttackTarget>,
+    /// The level of the character itself
+    pub level: u16,
+    /// The total attributes of the character itself
+    pub attributes: u32,
     pub max_level: u16,
     pub max_attributes: u32,
     pub blacklist: IntMap<u32, (String, usize)>,
     pub attack_log: Vec<(DateTime<Local>, AttackTarget, bool)>,
     pub auto_battle: bool,
+    pub scoring: TargetScoring,
+    pub filter: TargetFilter,
+    /// The filter shown in the UI. Text is typed into this and only copied
+    /// into `filter`, once it is applied
+    pub filter_draft: TargetFilter,
+    pub epic_mode: EpicMode,
+    pub score_script: String,
+    pub script: Option<ScoreScript>,
+    /// The reason, why the script could not be compiled, or evaluated
+    pub script_error: Option<String>,
+    /// The amount of items, that are not in the scrapbook, but equipped by
+    /// at least one player on the server
+    pub missing_available: usize,
+    /// Every item, that is not in the scrapbook, together with the amount of
+    /// crawled players, that have it equipped
+    pub missing_items: Vec<(EquipmentIdent, usize)>,
+    /// The amount of missing items, that at least one attackable player has
+    /// equipped
+    pub missing_attackable: usize,
+    /// The amount of new items each attackable player would give us. Epics
+    /// are weighted according to the epic mode
+    pub per_player_counts: IntMap<u32, usize>,
+    pub selected_item: Option<EquipmentIdent>,
+    /// Only items, whose name contains this, are shown on the items page
+    pub item_search: String,
+    /// The uid of the player shown in the detail pane of the scrapbook page
+    pub selected_target: Option<u32>,
 }
 
 impl ScrapbookInfo {
@@ -73,33 +128,87 @@ impl ScrapbookInfo {
         gs: &GameState,
         config: Option<&CharacterConfig>,
     ) -> Option<Self> {
-        let max_attributes = {
-            let base = gs.character.attribute_basis.as_array();
-            let bonus = gs.character.attribute_additions.as_array();
-            let total = base.iter().chain(bonus).sum::<u32>();
-            let expected_battle_luck = 1.2f32;
-            (total as f32 * expected_battle_luck) as u32
+        let attributes = total_attributes(gs);
+        let expected_battle_luck = 1.2f32;
+        let max_attributes = (attributes as f32 * expected_battle_luck) as u32;
+
+        let mut si = Self::from_scrapbook(
+            gs.character.scrapbook.as_ref()?.clone(),
+            gs.character.level,
+            max_attributes,
+            config,
+        );
+        si.level = gs.character.level;
+        si.attributes = attributes;
+        Some(si)
+    }
+
+    /// Creates the info from a scrapbook, that does not have to come from a
+    /// logged in character. The level & attributes of the character itself
+    /// are assumed to be the limits in that case
+    pub fn from_scrapbook(
+        scrapbook: ScrapBook,
+        max_level: u16,
+        max_attributes: u32,
+        config: Option<&CharacterConfig>,
+    ) -> Self {
+        let score_script =
+            config.map(|a| a.score_script.clone()).unwrap_or_default();
+        let (script, script_error) = match ScoreScript::compile(&score_script) {
+            Ok(script) => (Some(script), None),
+            Err(e) => (None, Some(e)),
         };
 
-        Some(Self {
-            scrapbook: gs.character.scrapbook.as_ref()?.clone(),
+        let filter = config
+            .map(|a| a.scrapbook_filter.clone())
+            .unwrap_or_default();
+        Self {
+            scrapbook,
             best: Default::default(),
-            max_level: gs.character.level,
+            level: max_level,
+            attributes: max_attributes,
+            max_level,
             max_attributes,
             blacklist: Default::default(),
             attack_log: Default::default(),
             auto_battle: config.map(|a| a.auto_battle).unwrap_or(false),
-        })
+            scoring: config.map(|a| a.scoring).unwrap_or_default(),
+            epic_mode: config.map(|a| a.epic_mode).unwrap_or_default(),
+            score_script,
+            script,
+            script_error,
+            filter_draft: filter.clone(),
+            filter,
+            missing_available: 0,
+            missing_items: Default::default(),
+            missing_attackable: 0,
+            per_player_counts: Default::default(),
+            selected_item: None,
+            item_search: String::new(),
+            selected_target: None,
+        }
     }
 }
 
+/// The sum of the base & bonus attributes of the character
+pub fn total_attributes(gs: &GameState) -> u32 {
+    let base = gs.character.attribute_basis.as_array();
+    let bonus = gs.character.attribute_additions.as_array();
+    base.iter().chain(bonus).sum()
+}
+
 impl AccountInfo {
     pub fn new(
         name: &str,
         auth: PlayerAuth,
         ident: AccountIdent,
+        server_ident: &str,
     ) -> AccountInfo {
         AccountInfo {
+            history: AccountHistory::load(server_ident, name),
+            lure_stats: None,
+            progress: ScrapbookProgress::load(server_ident, name),
+            economy: UnderworldEconomy::load(server_ident, name),
             name: name.to_string(),
             auth,
             scrapbook_info: None,
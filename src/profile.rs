@@ -0,0 +1,111 @@
+use std::{path::PathBuf, sync::OnceLock};
+
+use log::{error, info};
+
+const PROFILE_DIR: &str = "profiles";
+
+struct Profile {
+    name: Option<String>,
+    /// The directory the helper was started in. The profiles are relative to
+    /// this
+    root: PathBuf,
+}
+
+static ACTIVE: OnceLock<Profile> = OnceLock::new();
+
+/// An entry in the profile switcher
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileChoice(pub Option<String>);
+
+impl std::fmt::Display for ProfileChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0.as_deref().unwrap_or("Default"))
+    }
+}
+
+/// Profile names end up in paths, so we only allow a few harmless chars
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|a| a.is_ascii_alphanumeric() || a == '-' || a == '_')
+}
+
+/// Switches into the directory of the profile. Everything the helper writes
+/// (config, backups, history, etc.) is relative to the working directory, so
+/// this keeps the profiles completely separate from each other
+pub fn init(name: Option<String>) -> Result<(), String> {
+    let root = std::env::current_dir().map_err(|e| e.to_string())?;
+    if let Some(name) = &name {
+        if !is_valid_name(name) {
+            return Err(format!(
+                "Invalid profile name {name:?}. Only letters, digits, - and _ \
+                 are allowed"
+            ));
+        }
+        let dir = root.join(PROFILE_DIR).join(name);
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        std::env::set_current_dir(&dir).map_err(|e| e.to_string())?;
+    }
+    _ = ACTIVE.set(Profile { name, root });
+    Ok(())
+}
+
+/// The name of the active profile. `None` is the default profile
+pub fn active() -> Option<&'static str> {
+    ACTIVE.get().and_then(|a| a.name.as_deref())
+}
+
+/// Makes a path from the command line absolute, so that it still points to
+/// the same file after we switched into the profile directory
+pub fn absolute(path: PathBuf) -> PathBuf {
+    match ACTIVE.get() {
+        Some(profile) if path.is_relative() => profile.root.join(path),
+        _ => path,
+    }
+}
+
+/// All profiles, that have been created so far
+pub fn list() -> Vec<String> {
+    let Some(profile) = ACTIVE.get() else {
+        return vec![];
+    };
+    let Ok(dir) = std::fs::read_dir(profile.root.join(PROFILE_DIR)) else {
+        return vec![];
+    };
+    let mut res: Vec<_> = dir
+        .flatten()
+        .filter(|a| a.path().is_dir())
+        .filter_map(|a| a.file_name().into_string().ok())
+        .filter(|a| is_valid_name(a))
+        .collect();
+    res.sort();
+    res
+}
+
+/// Starts a new helper with the given profile and exits this one
+pub fn switch(name: Option<&str>) {
+    let Some(profile) = ACTIVE.get() else {
+        return;
+    };
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            error!("Could not find the helper executable: {e}");
+            return;
+        }
+    };
+    let mut cmd = std::process::Command::new(exe);
+    cmd.current_dir(&profile.root);
+    if let Some(name) = name {
+        cmd.args(["--profile", name]);
+    }
+    match cmd.spawn() {
+        Ok(_) => {
+            info!("Switching to profile {}", name.unwrap_or("default"));
+            crate::storage::flush();
+            std::process::exit(0);
+        }
+        Err(e) => error!("Could not start the helper: {e}"),
+    }
+}
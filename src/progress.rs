@@ -0,0 +1,175 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sf_api::gamestate::{unlockables::EquipmentIdent, GameState};
+
+use crate::{player::ScrapbookInfo, storage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressSnapshot {
+    pub items: usize,
+    pub level: u16,
+    pub attributes: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChartMode {
+    #[default]
+    Progress,
+    PerDay,
+}
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for ChartMode {
+    fn to_string(&self) -> String {
+        match self {
+            ChartMode::Progress => "Progress",
+            ChartMode::PerDay => "Items per day",
+        }
+        .to_string()
+    }
+}
+
+/// Everything needed to calculate the targets of a character without
+/// logging in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrapbookSnapshot {
+    pub time: DateTime<Local>,
+    pub max_level: u16,
+    pub max_attributes: u32,
+    pub items: Vec<EquipmentIdent>,
+}
+
+impl ScrapbookSnapshot {
+    pub fn path(server_ident: &str, name: &str) -> PathBuf {
+        storage::char_path("scrapbooks", server_ident, name, "json")
+    }
+
+    pub fn read(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Daily snapshots of the scrapbook of a single character. Only the newest
+/// snapshot of each day is kept
+pub struct ScrapbookProgress {
+    path: PathBuf,
+    pub days: BTreeMap<NaiveDate, ProgressSnapshot>,
+    pub chart_mode: ChartMode,
+    scrapbook_path: PathBuf,
+    /// The items, max level & max attributes of the last saved scrapbook
+    saved_scrapbook: Option<(usize, u16, u32)>,
+}
+
+impl ScrapbookProgress {
+    pub fn load(server_ident: &str, name: &str) -> ScrapbookProgress {
+        let path = storage::char_path("progress", server_ident, name, "json");
+        let days = storage::read_json(&path).unwrap_or_default();
+
+        ScrapbookProgress {
+            path,
+            days,
+            chart_mode: ChartMode::Progress,
+            scrapbook_path: ScrapbookSnapshot::path(server_ident, name),
+            saved_scrapbook: None,
+        }
+    }
+
+    /// Updates the snapshot of the current day and saves it, if anything
+    /// changed
+    pub fn record(&mut self, items: usize, gs: &GameState) {
+        let attributes = gs
+            .character
+            .attribute_basis
+            .as_array()
+            .iter()
+            .chain(gs.character.attribute_additions.as_array())
+            .sum::<u32>();
+
+        let snapshot = ProgressSnapshot {
+            items,
+            level: gs.character.level,
+            attributes,
+        };
+
+        let today = Local::now().date_naive();
+        if self.days.get(&today) == Some(&snapshot) {
+            return;
+        }
+        self.days.insert(today, snapshot);
+        storage::write_json(&self.path, &self.days);
+    }
+
+    /// Saves the full scrapbook, so that the `query` command can use it. Only
+    /// writes to disk, if something changed since the last save
+    pub fn save_scrapbook(&mut self, si: &ScrapbookInfo) {
+        let current =
+            (si.scrapbook.items.len(), si.max_level, si.max_attributes);
+        if self.saved_scrapbook == Some(current) {
+            return;
+        }
+        let snapshot = ScrapbookSnapshot {
+            time: Local::now(),
+            max_level: si.max_level,
+            max_attributes: si.max_attributes,
+            items: si.scrapbook.items.iter().copied().collect(),
+        };
+        storage::write_json(&self.scrapbook_path, &snapshot);
+        self.saved_scrapbook = Some(current);
+    }
+
+    /// The amount of items gained on each day. Days without a snapshot get an
+    /// equal share of the items gained until the next one, so that a gap
+    /// does not show up as a spike on the day after it
+    pub fn items_per_day(&self) -> Vec<(NaiveDate, f32)> {
+        let mut res = vec![];
+        for ((old_date, old), (date, new)) in
+            self.days.iter().zip(self.days.iter().skip(1))
+        {
+            let gap = (*date - *old_date).num_days().max(1);
+            let gained = new.items.saturating_sub(old.items) as f32;
+            for day in 1..=gap {
+                res.push((
+                    *old_date + chrono::Duration::days(day),
+                    gained / gap as f32,
+                ));
+            }
+        }
+        res
+    }
+
+    /// The average amount of items gained per day within the last `days` days
+    pub fn avg_per_day(&self, days: i64) -> Option<f32> {
+        let (last_date, last) = self.days.last_key_value()?;
+        let start = *last_date - chrono::Duration::days(days);
+        let (first_date, first) = self.days.range(start..).next()?;
+        let elapsed = (*last_date - *first_date).num_days();
+        if elapsed <= 0 {
+            return None;
+        }
+        Some(last.items.saturating_sub(first.items) as f32 / elapsed as f32)
+    }
+
+    /// The estimated date, at which all items, that are currently available on
+    /// the server, would be collected
+    pub fn projected_completion(
+        &self,
+        missing_available: usize,
+    ) -> Option<(NaiveDate, usize)> {
+        let (last_date, last) = self.days.last_key_value()?;
+        let rate = self.avg_per_day(14)?;
+        if rate <= 0.0 {
+            return None;
+        }
+        let days = (missing_available as f32 / rate).ceil() as i64;
+        Some((
+            *last_date + chrono::Duration::days(days.min(365 * 20)),
+            last.items + missing_available,
+        ))
+    }
+}
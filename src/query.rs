@@ -0,0 +1,226 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use iced::{futures::executor::block_on, Executor};
+use serde::Serialize;
+use sf_api::gamestate::unlockables::{EquipmentIdent, ScrapBook};
+
+use crate::{
+    backup::{restore_backup, ZHofBackup},
+    calc_battle_order, calc_per_player_count,
+    config::Config,
+    history::{AccountHistory, HistoryEvent},
+    player::ScrapbookInfo,
+    profile,
+    progress::ScrapbookSnapshot,
+    rank_targets,
+    server::ServerIdent,
+};
+
+#[derive(Debug, clap::Args, Clone)]
+pub struct QueryArgs {
+    /// The url of the server, whose local .zhof backup should be used
+    #[arg(short, long)]
+    server: String,
+    /// The character, whose saved scrapbook should be used. The settings
+    /// and lost fights of this character are applied as well
+    #[arg(short, long, required_unless_present = "items")]
+    character: Option<String>,
+    /// A json list of item idents, that are used as the scrapbook instead
+    #[arg(short, long)]
+    items: Option<PathBuf>,
+    /// Ignore players above this level
+    #[arg(long)]
+    max_level: Option<u16>,
+    /// Ignore players with more total attributes than this
+    #[arg(long)]
+    max_attributes: Option<u32>,
+    /// Ignore players, that won this many fights against the character
+    #[arg(long)]
+    blacklist: Option<usize>,
+    /// The maximum amount of targets to print
+    #[arg(short, long, default_value_t = 20)]
+    limit: usize,
+    /// Print the optimal battle order instead of the ranked targets
+    #[arg(long)]
+    order: bool,
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct QueryTarget<'a> {
+    name: &'a str,
+    uid: u32,
+    level: u16,
+    attributes: Option<u32>,
+    missing: usize,
+    score: f32,
+}
+
+/// Calculates the best targets from a local backup without logging in and
+/// prints them to stdout
+pub fn run(args: QueryArgs) -> iced::Result {
+    if let Err(e) = query(args) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn query(args: QueryArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::restore()?;
+    let server = ServerIdent::new(&args.server);
+
+    // The backup is read with tokio, so we need to be within a runtime
+    let executor = iced::executor::Default::new()?;
+    let backup = executor
+        .enter(|| block_on(ZHofBackup::read(&server.ident)))
+        .map_err(|e| format!("Could not read {}.zhof: {e}", server.ident))?;
+    let data = executor.enter(|| {
+        block_on(restore_backup(
+            Some(Box::new(backup)),
+            0,
+            config.lure_rules.clone(),
+        ))
+    });
+
+    let snapshot = match &args.character {
+        Some(name) => {
+            let path = ScrapbookSnapshot::path(&server.ident, name);
+            let snapshot = ScrapbookSnapshot::read(&path).map_err(|e| {
+                format!("Could not read the scrapbook of {name}: {e}")
+            })?;
+            Some(snapshot)
+        }
+        None => None,
+    };
+
+    let items: HashSet<EquipmentIdent> = match &args.items {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(
+            profile::absolute(path.clone()),
+        )?)?,
+        None => snapshot
+            .as_ref()
+            .map(|a| a.items.iter().copied().collect())
+            .unwrap_or_default(),
+    };
+
+    let char_conf = args
+        .character
+        .as_ref()
+        .and_then(|name| config.get_char_conf(name, server.id));
+
+    let mut si = ScrapbookInfo::from_scrapbook(
+        ScrapBook {
+            items,
+            ..Default::default()
+        },
+        args.max_level
+            .or(snapshot.as_ref().map(|a| a.max_level))
+            .unwrap_or(u16::MAX),
+        args.max_attributes
+            .or(snapshot.as_ref().map(|a| a.max_attributes))
+            .unwrap_or(u32::MAX),
+        char_conf,
+    );
+
+    if let Some(name) = &args.character {
+        let history = AccountHistory::load(&server.ident, name);
+        for entry in &history.entries {
+            if let HistoryEvent::Fight {
+                target,
+                uid,
+                won: false,
+                ..
+            } = &entry.event
+            {
+                si.blacklist.entry(*uid).or_insert((target.clone(), 0)).1 += 1;
+            }
+        }
+    }
+
+    let blacklist_th = args.blacklist.unwrap_or(config.blacklist_threshold);
+    let invalid = data.invalid_accounts.iter().map(|a| a.as_str()).collect();
+    let per_player_counts = calc_per_player_count(
+        &data.player_info, &data.equipment, &si.scrapbook.items, &si,
+        blacklist_th,
+    );
+
+    let limit = if args.order { 1 } else { args.limit };
+    let best = rank_targets(
+        &mut si, &per_player_counts, &data.equipment, &data.player_info, limit,
+        &invalid,
+    );
+    if let Some(e) = &si.script_error {
+        eprintln!("{e}");
+    }
+
+    if args.order {
+        let mut order = calc_battle_order(
+            best.into_iter().next(),
+            &data.player_info,
+            &data.equipment,
+            &si,
+            &invalid,
+            blacklist_th,
+        );
+        order.truncate(args.limit);
+        match args.format {
+            OutputFormat::Table => {
+                for (pos, name) in order.iter().enumerate() {
+                    println!("{:>4}  {name}", pos + 1);
+                }
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&order)?);
+            }
+        }
+        return Ok(());
+    }
+
+    let targets: Vec<_> = best
+        .iter()
+        .map(|a| QueryTarget {
+            name: &a.info.name,
+            uid: a.info.uid,
+            level: a.info.level,
+            attributes: a.info.stats,
+            missing: a.missing,
+            score: a.score,
+        })
+        .collect();
+
+    match args.format {
+        OutputFormat::Table => {
+            println!(
+                "{:>4}  {:<20} {:>5} {:>10} {:>7} {:>8}",
+                "#", "Name", "Level", "Attributes", "Missing", "Score"
+            );
+            for (pos, target) in targets.iter().enumerate() {
+                let attributes = target
+                    .attributes
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|| "?".to_string());
+                println!(
+                    "{:>4}  {:<20} {:>5} {:>10} {:>7} {:>8.2}",
+                    pos + 1,
+                    target.name,
+                    target.level,
+                    attributes,
+                    target.missing,
+                    target.score
+                );
+            }
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&targets)?);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use chrono::Utc;
+use rhai::{Dynamic, Engine, Scope, AST};
+
+use crate::{player::ScrapbookInfo, CharacterInfo};
+
+/// The maximum amount of operations a single evaluation may take. Protects
+/// the ui from scripts, that loop forever
+const MAX_OPERATIONS: u64 = 10_000;
+
+/// All variables, that are available to a scoring script
+pub const SCRIPT_VARIABLES: &str = "level, class, attributes, items, \
+                                    missing, rarity, guild, name, age, \
+                                    my_level, my_attributes, max_level, \
+                                    max_attributes";
+
+/// A compiled, user provided script, that calculates the score of a
+/// scrapbook target
+pub struct ScoreScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl std::fmt::Debug for ScoreScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScoreScript").finish_non_exhaustive()
+    }
+}
+
+impl ScoreScript {
+    pub fn compile(source: &str) -> Result<ScoreScript, String> {
+        if source.trim().is_empty() {
+            return Err("The script is empty".to_string());
+        }
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| format!("Invalid script: {e}"))?;
+        Ok(ScoreScript { engine, ast })
+    }
+
+    /// Prepares the evaluation of the script for the targets of a character
+    pub fn scorer(&self, si: &ScrapbookInfo) -> Scorer<'_> {
+        let mut scope = Scope::new();
+        // Constants, so that a script can not change them for the targets,
+        // that are scored after the current one
+        scope.push_constant("my_level", si.level as i64);
+        scope.push_constant("my_attributes", si.attributes as i64);
+        scope.push_constant("max_level", si.max_level as i64);
+        scope.push_constant("max_attributes", si.max_attributes as i64);
+        let base = scope.len();
+        Scorer {
+            script: self,
+            scope,
+            base,
+        }
+    }
+}
+
+/// Scores the targets of a single character. The scope is reused for every
+/// target, so that only the variables of the target itself have to be set
+pub struct Scorer<'a> {
+    script: &'a ScoreScript,
+    scope: Scope<'static>,
+    /// The length of the scope, before the variables of a target are added
+    base: usize,
+}
+
+impl Scorer<'_> {
+    /// Evaluates the script for a single target. Higher scores are better
+    pub fn score(
+        &mut self,
+        info: &CharacterInfo,
+        missing: usize,
+        rarity: f32,
+    ) -> Result<f32, String> {
+        // Removes the target variables of the last run, including everything
+        // the script defined itself
+        self.scope.rewind(self.base);
+        let scope = &mut self.scope;
+        scope.push("level", info.level as i64);
+        scope.push(
+            "class",
+            info.class.map(|a| format!("{a:?}")).unwrap_or_default(),
+        );
+        scope.push("attributes", info.stats.map_or(-1, |a| a as i64));
+        scope.push("items", info.equipment.len() as i64);
+        scope.push("missing", missing as i64);
+        scope.push("rarity", rarity as f64);
+        scope.push("guild", info.guild.clone().unwrap_or_default());
+        scope.push("name", info.name.clone());
+        scope.push(
+            "age",
+            info.fetch_date.map_or(-1, |a| (Utc::now() - a).num_days()),
+        );
+
+        let res: Dynamic = self
+            .script
+            .engine
+            .eval_ast_with_scope(scope, &self.script.ast)
+            .map_err(|e| format!("Script failed for {}: {e}", info.name))?;
+
+        if let Ok(res) = res.as_float() {
+            return Ok(res as f32);
+        }
+        if let Ok(res) = res.as_int() {
+            return Ok(res as f32);
+        }
+        Err(format!(
+            "The script has to return a number, not {}",
+            res.type_name()
+        ))
+    }
+}
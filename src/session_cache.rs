@@ -0,0 +1,177 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, Once},
+};
+
+use chacha20poly1305::{
+    aead::{KeyInit, OsRng},
+    ChaCha20Poly1305, Key,
+};
+use log::{debug, warn};
+use sf_api::{command::Command, gamestate::GameState, session::Session};
+
+use crate::{
+    server::ServerIdent,
+    storage,
+    vault::{self, decrypt, encrypt},
+};
+
+const KEY_FILE: &str = "session.key";
+const SESSION_DIR: &str = "sessions";
+
+static KEY: Mutex<Option<Key>> = Mutex::new(None);
+
+/// Game sessions are cached on disk, so that a restart can continue using
+/// them instead of logging in again. The cache is encrypted with a random
+/// key, which is wrapped with the key of the credential vault. Without an
+/// unlocked vault, there would be nowhere safe to keep that key, so sessions
+/// are not cached at all
+fn key() -> Option<Key> {
+    if !vault::exists() {
+        // Older versions stored the key in plain text
+        static CLEANUP: Once = Once::new();
+        CLEANUP.call_once(clear);
+    }
+    if !vault::is_unlocked() {
+        return None;
+    }
+    let mut lock = KEY.lock().unwrap();
+    if let Some(key) = *lock {
+        return Some(key);
+    }
+    let key = match read_key() {
+        Some(key) => key,
+        None => {
+            // Sessions encrypted with a previous key can not be decrypted
+            // anymore and are removed, once they are loaded
+            let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+            write_key(&key)?;
+            key
+        }
+    };
+    *lock = Some(key);
+    Some(key)
+}
+
+/// Reads the key, that is wrapped with the vault
+fn read_key() -> Option<Key> {
+    let bytes = match std::fs::read(KEY_FILE) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            warn!("Could not read the session key: {e}. Using a new one");
+            return None;
+        }
+    };
+    match vault::unwrap_key(&bytes) {
+        Some(bytes) if bytes.len() == 32 => Some(*Key::from_slice(&bytes)),
+        _ => {
+            warn!("The session key is invalid. Using a new one");
+            None
+        }
+    }
+}
+
+fn write_key(key: &Key) -> Option<()> {
+    let data = vault::wrap_key(key)?;
+    if let Err(e) = std::fs::write(KEY_FILE, data) {
+        warn!("Could not write the session key: {e}");
+        return None;
+    }
+    Some(())
+}
+
+/// Enables, or disables the vault with `change`. Sessions cached before
+/// can not be read with the new setup, so they are removed, if that succeeded
+pub fn change_vault<T, E>(
+    change: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let res = change()?;
+    clear();
+    Ok(res)
+}
+
+/// Removes the key and every cached session
+fn clear() {
+    *KEY.lock().unwrap() = None;
+    for path in [KEY_FILE, SESSION_DIR] {
+        let path = Path::new(path);
+        let res = match path.is_dir() {
+            true => std::fs::remove_dir_all(path),
+            false => std::fs::remove_file(path),
+        };
+        if let Err(e) = res {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Could not remove {path:?}: {e}");
+            }
+        }
+    }
+}
+
+fn path(server_ident: &str, name: &str) -> PathBuf {
+    storage::char_path(SESSION_DIR, server_ident, name, "bin")
+}
+
+/// Loads the cached session of the character, if there is one
+pub fn load(server_ident: &str, name: &str) -> Option<Session> {
+    let key = key()?;
+    let data = std::fs::read(path(server_ident, name)).ok()?;
+    let Some(data) = decrypt(&key, &data) else {
+        warn!("Could not decrypt the cached session of {name}");
+        remove(server_ident, name);
+        return None;
+    };
+    serde_json::from_slice(&data).ok()
+}
+
+/// Caches the session, so that it can be reused after a restart. Returns
+/// false, if it could not be cached, which is always the case without an
+/// unlocked vault
+pub fn store(session: &Session) -> bool {
+    let Some(key) = key() else {
+        return false;
+    };
+    let server_ident = ServerIdent::new(session.server_url().as_str()).ident;
+    let path = path(&server_ident, session.username());
+
+    let res = (|| -> Result<(), Box<dyn std::error::Error>> {
+        let data = serde_json::to_vec(session)?;
+        let data = encrypt(&key, &data).ok_or("Could not encrypt")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, data)?;
+        Ok(())
+    })();
+    if let Err(e) = &res {
+        warn!("Could not cache the session in {path:?}: {e}");
+    }
+    res.is_ok()
+}
+
+pub fn remove(server_ident: &str, name: &str) {
+    _ = std::fs::remove_file(path(server_ident, name));
+}
+
+/// Checks if the cached session is still valid by requesting the newest
+/// state from the server. Returns `None`, if it expired and a full login is
+/// needed
+pub async fn resume(session: &mut Session) -> Option<GameState> {
+    let resp = match session.send_command(&Command::Update).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            debug!("Cached session of {} expired: {e}", session.username());
+            return None;
+        }
+    };
+    match GameState::new(resp) {
+        Ok(gs) => {
+            debug!("Resumed the cached session of {}", session.username());
+            Some(gs)
+        }
+        Err(e) => {
+            debug!("Could not resume {}: {e}", session.username());
+            None
+        }
+    }
+}
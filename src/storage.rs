@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex, OnceLock,
+    },
+};
+
+use log::warn;
+use serde::{de::DeserializeOwned, Serialize};
+
+enum Job {
+    /// Replaces the whole file
+    Write(PathBuf, String),
+    /// Adds a line to the end of the file
+    Append(PathBuf, String),
+    /// Signals, once everything before it has been written
+    Flush(Sender<()>),
+}
+
+static WRITER: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+
+/// The file of a character in `dir`. Character names come from the server, so
+/// everything, that could leave the directory, is replaced
+pub fn char_path(
+    dir: &str,
+    server_ident: &str,
+    name: &str,
+    ext: &str,
+) -> PathBuf {
+    let name: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    Path::new(dir).join(format!("{server_ident}-{name}.{ext}"))
+}
+
+/// Reads a json file. A missing file is not worth a warning, a broken one is
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(res) => Some(res),
+        Err(e) => {
+            warn!("Could not read {path:?}: {e}");
+            None
+        }
+    }
+}
+
+/// Reads a file with one json value per line. Invalid lines are skipped
+pub fn read_json_lines<T: DeserializeOwned>(path: &Path) -> Vec<T> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return vec![];
+    };
+    content
+        .lines()
+        .filter(|a| !a.trim().is_empty())
+        .filter_map(|a| match serde_json::from_str(a) {
+            Ok(res) => Some(res),
+            Err(e) => {
+                warn!("Skipping an invalid line in {path:?}: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Replaces the file with the serialized value in the background. If the same
+/// file is written multiple times before the writer gets to it, only the
+/// newest value is written
+pub fn write_json<T: Serialize>(path: &Path, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(content) => send(Job::Write(path.to_path_buf(), content)),
+        Err(e) => warn!("Could not serialize {path:?}: {e}"),
+    }
+}
+
+/// Appends the serialized value as a new line in the background
+pub fn append_json<T: Serialize>(path: &Path, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(line) => send(Job::Append(path.to_path_buf(), line)),
+        Err(e) => warn!("Could not serialize {path:?}: {e}"),
+    }
+}
+
+/// Blocks until everything, that has been queued so far, is on disk. Has to
+/// be called before the helper exits
+pub fn flush() {
+    let (tx, rx) = mpsc::channel();
+    send(Job::Flush(tx));
+    _ = rx.recv();
+}
+
+fn send(job: Job) {
+    let writer = WRITER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || run_writer(rx));
+        Mutex::new(tx)
+    });
+    _ = writer.lock().unwrap().send(job);
+}
+
+fn run_writer(rx: Receiver<Job>) {
+    while let Ok(job) = rx.recv() {
+        // Everything, that piled up in the meantime, is handled at once, so
+        // that files, which change often, are only written once
+        let mut jobs = vec![job];
+        jobs.extend(rx.try_iter());
+
+        let mut latest: HashMap<&Path, usize> = HashMap::new();
+        for (pos, job) in jobs.iter().enumerate() {
+            if let Job::Write(path, _) = job {
+                latest.insert(path, pos);
+            }
+        }
+
+        let mut flushed = vec![];
+        for (pos, job) in jobs.iter().enumerate() {
+            let (path, res) = match job {
+                Job::Write(path, _)
+                    if latest.get(path.as_path()) != Some(&pos) =>
+                {
+                    continue;
+                }
+                Job::Write(path, content) => (path, write_file(path, content)),
+                Job::Append(path, line) => (path, append_line(path, line)),
+                Job::Flush(done) => {
+                    flushed.push(done);
+                    continue;
+                }
+            };
+            if let Err(e) = res {
+                warn!("Could not write {path:?}: {e}");
+            }
+        }
+        for done in flushed {
+            _ = done.send(());
+        }
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)
+}
+
+fn append_line(path: &Path, line: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
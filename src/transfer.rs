@@ -0,0 +1,324 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sf_api::session::PWHash;
+
+use crate::{
+    config::{AccountConfig, AccountCreds, Config},
+    profile,
+    server::ServerIdent,
+    vault,
+};
+
+/// The start of an export, that has been encrypted with a passphrase
+const SEALED_MAGIC: &[u8; 4] = b"SFEX";
+const EXPORT_VERSION: u32 = 1;
+/// The passphrase of encrypted exports. Read from the environment, so that it
+/// does not show up in the arguments of the process
+pub const PASSPHRASE_ENV: &str = "SF_EXPORT_PASSPHRASE";
+/// A file descriptor, from which the passphrase of encrypted exports is read
+pub const PASSPHRASE_FD_ENV: &str = "SF_EXPORT_PASSPHRASE_FD";
+
+#[derive(Debug, clap::Subcommand, Clone)]
+pub enum AccountsCommand {
+    /// Writes all saved accounts and their settings to a portable json file
+    Export {
+        file: PathBuf,
+        /// Encrypt the export with the passphrase from SF_EXPORT_PASSPHRASE,
+        /// or the file descriptor in SF_EXPORT_PASSPHRASE_FD
+        #[arg(long)]
+        encrypt: bool,
+    },
+    /// Adds the accounts of an export to the saved accounts. Files ending in
+    /// `.csv` are read as `name,server,password` lines instead. The
+    /// passphrase of an encrypted export is read like the one of `export`
+    Import {
+        file: PathBuf,
+        /// Overwrite accounts, that are already saved, instead of skipping
+        /// them
+        #[arg(long)]
+        replace: bool,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AccountExport {
+    version: u32,
+    accounts: Vec<AccountConfig>,
+}
+
+pub fn run(cmd: AccountsCommand) -> iced::Result {
+    let res = match cmd {
+        AccountsCommand::Export { file, encrypt } => {
+            export(&profile::absolute(file), encrypt)
+        }
+        AccountsCommand::Import { file, replace } => {
+            import(&profile::absolute(file), replace)
+        }
+    };
+    if let Err(e) = res {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Reads the config with all credentials resolved, so that we neither export
+/// vault references, nor write new credentials in plain text next to them
+fn unlocked_config() -> Result<Config, Box<dyn std::error::Error>> {
+    vault::unlock_from_env()?;
+    if vault::exists() && !vault::is_unlocked() {
+        return Err(format!(
+            "The credential vault is locked. Set {} or {} to unlock it",
+            vault::PASSWORD_ENV,
+            vault::PASSWORD_FD_ENV
+        )
+        .into());
+    }
+    let config = Config::restore()?;
+    if config.is_locked() && !vault::exists() {
+        return Err(vault::VaultError::Missing.into());
+    }
+    if config.is_locked() {
+        return Err("Some credentials are missing from the vault".into());
+    }
+    Ok(config)
+}
+
+fn passphrase() -> Result<String, String> {
+    vault::secret_from_env(PASSPHRASE_ENV, PASSPHRASE_FD_ENV).ok_or_else(|| {
+        format!("Set {PASSPHRASE_ENV} or {PASSPHRASE_FD_ENV} to the passphrase")
+    })
+}
+
+fn export(
+    path: &Path,
+    encrypt: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let passphrase = match encrypt {
+        true => Some(passphrase()?),
+        false => None,
+    };
+    let config = unlocked_config()?;
+    let export = AccountExport {
+        version: EXPORT_VERSION,
+        accounts: config.accounts,
+    };
+    let mut data = serde_json::to_vec_pretty(&export)?;
+    if let Some(passphrase) = passphrase {
+        let mut sealed = SEALED_MAGIC.to_vec();
+        sealed.extend(vault::seal(&passphrase, &data)?);
+        data = sealed;
+    }
+    std::fs::write(path, data)?;
+    println!(
+        "Exported {} account(s) to {}",
+        export.accounts.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn import(
+    path: &Path,
+    replace: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = unlocked_config()?;
+    let data = std::fs::read(path)?;
+
+    let is_csv = path
+        .extension()
+        .is_some_and(|a| a.eq_ignore_ascii_case("csv"));
+    let accounts = if is_csv {
+        read_csv(&String::from_utf8(data)?)?
+    } else {
+        read_export(&data)?
+    };
+
+    let (mut added, mut replaced, mut skipped) = (0, 0, 0);
+    for acc in accounts {
+        let key = acc.key();
+        match config.accounts.iter().position(|a| a.key() == key) {
+            Some(pos) if replace => {
+                config.accounts[pos] = acc;
+                replaced += 1;
+            }
+            Some(_) => skipped += 1,
+            None => {
+                config.accounts.push(acc);
+                added += 1;
+            }
+        }
+    }
+    config.write()?;
+    println!(
+        "Imported {added} account(s), replaced {replaced}, skipped {skipped} \
+         already saved"
+    );
+    Ok(())
+}
+
+fn read_export(
+    data: &[u8],
+) -> Result<Vec<AccountConfig>, Box<dyn std::error::Error>> {
+    let data = match data.strip_prefix(SEALED_MAGIC) {
+        Some(sealed) => {
+            let passphrase = passphrase()
+                .map_err(|e| format!("This export is encrypted. {e}"))?;
+            vault::unseal(&passphrase, sealed)?
+        }
+        None => data.to_vec(),
+    };
+    let export: AccountExport = serde_json::from_slice(&data)?;
+    if export.version > EXPORT_VERSION {
+        return Err(format!(
+            "The export has version {}, but only {EXPORT_VERSION} is \
+             supported. Please update",
+            export.version
+        )
+        .into());
+    }
+    Ok(export.accounts)
+}
+
+/// Reads `name,server,password` lines. A header line, empty lines and lines
+/// starting with `#` are skipped. Every field can be quoted with `"`, which
+/// allows commas in it and `""` for a literal quote. Quoted fields can not
+/// span multiple lines. An unquoted password is the rest of the line, so it
+/// may contain commas. It is taken verbatim, so a password starting with
+/// whitespace, or a quote has to be quoted
+fn read_csv(content: &str) -> Result<Vec<AccountConfig>, String> {
+    let mut res = vec![];
+    for (pos, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if pos == 0 && line.to_lowercase().starts_with("name,") {
+            continue;
+        }
+        let (name, server, password) = read_csv_line(line)
+            .map_err(|e| format!("Line {}: {e}", pos + 1))?;
+        if name.is_empty() || server.is_empty() || password.is_empty() {
+            return Err(format!("Line {}: empty field", pos + 1));
+        }
+        res.push(AccountConfig::new(AccountCreds::Regular {
+            name: name.to_lowercase(),
+            pw_hash: PWHash::new(&password),
+            server: ServerIdent::new(&server).url,
+        }));
+    }
+    Ok(res)
+}
+
+/// Splits a line into name, server & password
+fn read_csv_line(line: &str) -> Result<(String, String, String), String> {
+    const EXPECTED: &str = "expected name,server,password";
+
+    let (name, rest) = csv_field(line)?;
+    let rest = rest.ok_or(EXPECTED)?;
+    let (server, rest) = csv_field(rest)?;
+    let password = rest.ok_or(EXPECTED)?;
+
+    let password = if password.starts_with('"') {
+        match csv_field(password)? {
+            (password, None) => password,
+            (_, Some(_)) => {
+                return Err("unexpected text after the quoted password".into())
+            }
+        }
+    } else if password.starts_with(char::is_whitespace) {
+        return Err("the password starts with whitespace. Quote it, if that \
+                    is intended"
+            .into());
+    } else {
+        password.to_string()
+    };
+    Ok((name.trim().to_string(), server.trim().to_string(), password))
+}
+
+/// Reads the first field of `line`. Returns the field and everything after
+/// the comma, that ends it, if there is one
+fn csv_field(line: &str) -> Result<(String, Option<&str>), String> {
+    let trimmed = line.trim_start();
+    let Some(quoted) = trimmed.strip_prefix('"') else {
+        return Ok(match line.split_once(',') {
+            Some((field, rest)) => (field.to_string(), Some(rest)),
+            None => (line.to_string(), None),
+        });
+    };
+
+    let mut field = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((pos, c)) = chars.next() {
+        if c != '"' {
+            field.push(c);
+            continue;
+        }
+        let rest = &quoted[pos + 1..];
+        if rest.starts_with('"') {
+            // An escaped quote
+            field.push('"');
+            chars.next();
+            continue;
+        }
+        let rest = rest.trim_start_matches([' ', '\t']);
+        return match rest.strip_prefix(',') {
+            Some(rest) => Ok((field, Some(rest))),
+            None if rest.is_empty() => Ok((field, None)),
+            None => Err("unexpected text after a quoted field".into()),
+        };
+    }
+    Err("a quoted field is not closed".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(name: &str, server: &str, pw: &str) -> (String, String, String) {
+        (name.into(), server.into(), pw.into())
+    }
+
+    #[test]
+    fn reads_plain_lines() {
+        assert_eq!(
+            read_csv_line("name,s1.sfgame.net,pw").unwrap(),
+            fields("name", "s1.sfgame.net", "pw")
+        );
+        // An unquoted password is the rest of the line
+        assert_eq!(
+            read_csv_line("name,s1.sfgame.net,p,w\"").unwrap(),
+            fields("name", "s1.sfgame.net", "p,w\"")
+        );
+    }
+
+    #[test]
+    fn reads_quoted_fields() {
+        assert_eq!(
+            read_csv_line(r#""na,me", "s1.sfgame.net","p""w,"" ""#).unwrap(),
+            fields("na,me", "s1.sfgame.net", r#"p"w," "#)
+        );
+        assert_eq!(
+            read_csv_line(r#"name,s1.sfgame.net," pw""#).unwrap(),
+            fields("name", "s1.sfgame.net", " pw")
+        );
+    }
+
+    #[test]
+    fn rejects_broken_lines() {
+        assert!(read_csv_line(r#"a,b,"pw",extra"#).is_err());
+        assert!(read_csv_line(r#"a,b,"pw"#).is_err());
+        assert!(read_csv_line(r#""a"x,b,pw"#).is_err());
+        assert!(read_csv_line("a,b, pw").is_err());
+        assert!(read_csv_line("a,b").is_err());
+    }
+
+    #[test]
+    fn skips_header_and_comments() {
+        let content =
+            "name,server,password\n# comment\n\na,s1.sfgame.net,pw\r\n";
+        assert_eq!(read_csv(content).unwrap().len(), 1);
+        assert!(read_csv("a,,pw").is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use iced::{
+    alignment::Horizontal,
+    theme,
+    widget::{button, column, pick_list, row, text, text_input, Button},
+    Alignment, Element, Length,
+};
+use iced_aw::number_input;
+use sf_api::gamestate::character::Class;
+
+use crate::{
+    config::{SortColumn, TargetFilter},
+    message::Message,
+    AccountIdent,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ClassChoice(Option<Class>);
+
+#[allow(clippy::to_string_trait_impl)]
+impl ToString for ClassChoice {
+    fn to_string(&self) -> String {
+        match self.0 {
+            Some(class) => format!("{class:?}"),
+            None => "Any".to_string(),
+        }
+    }
+}
+
+const CLASS_CHOICES: [ClassChoice; 12] = [
+    ClassChoice(None),
+    ClassChoice(Some(Class::Warrior)),
+    ClassChoice(Some(Class::Mage)),
+    ClassChoice(Some(Class::Scout)),
+    ClassChoice(Some(Class::Assassin)),
+    ClassChoice(Some(Class::BattleMage)),
+    ClassChoice(Some(Class::Berserker)),
+    ClassChoice(Some(Class::DemonHunter)),
+    ClassChoice(Some(Class::Druid)),
+    ClassChoice(Some(Class::Bard)),
+    ClassChoice(Some(Class::Necromancer)),
+    ClassChoice(Some(Class::Paladin)),
+];
+
+/// The filter bar above the target lists of the scrapbook & underworld. Shows
+/// the draft of the filter, so that text can be typed before it is applied
+pub fn view_filter(
+    ident: AccountIdent,
+    filter: &TargetFilter,
+    underworld: bool,
+) -> Element<'_, Message> {
+    let set = move |filter: TargetFilter| Message::PlayerSetFilter {
+        ident,
+        underworld,
+        filter,
+    };
+    let edit = move |filter: TargetFilter| Message::PlayerEditFilter {
+        ident,
+        underworld,
+        filter,
+    };
+
+    let class =
+        pick_list(CLASS_CHOICES, Some(ClassChoice(filter.class)), move |nv| {
+            set(TargetFilter {
+                class: nv.0,
+                ..filter.clone()
+            })
+        })
+        .width(Length::Fixed(120.0));
+
+    let min_level = number_input(filter.min_level, 9999, move |nv| {
+        set(TargetFilter {
+            min_level: nv,
+            ..filter.clone()
+        })
+    })
+    .style(iced_aw::NumberInputStyles::Default);
+
+    let max_age = number_input(filter.max_age, 365, move |nv| {
+        set(TargetFilter {
+            max_age: nv,
+            ..filter.clone()
+        })
+    })
+    .style(iced_aw::NumberInputStyles::Default);
+
+    // The text is only applied on enter, because every change re-ranks all
+    // players
+    let guild = text_input("Guild", &filter.guild)
+        .on_input(move |nv| {
+            edit(TargetFilter {
+                guild: nv,
+                ..filter.clone()
+            })
+        })
+        .on_submit(set(filter.clone()));
+
+    let name = text_input("Name (* and ?)", &filter.name)
+        .on_input(move |nv| {
+            edit(TargetFilter {
+                name: nv,
+                ..filter.clone()
+            })
+        })
+        .on_submit(set(filter.clone()));
+
+    let mut top = row!(text("Class:"), class, text("Min Level:"), min_level,)
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+    if !underworld {
+        let min_missing = number_input(filter.min_missing, 99, move |nv| {
+            set(TargetFilter {
+                min_missing: nv,
+                ..filter.clone()
+            })
+        })
+        .style(iced_aw::NumberInputStyles::Default);
+        top = top.push(text("Min Missing:")).push(min_missing);
+    }
+
+    top = top.push(text("Max Age (days):")).push(max_age);
+
+    let cleared = TargetFilter {
+        sort: filter.sort,
+        ..Default::default()
+    };
+    let reset = button("Reset")
+        .on_press_maybe((*filter != cleared).then(|| set(cleared)))
+        .style(theme::Button::Destructive)
+        .padding(4);
+
+    let bottom = row!(guild, name, reset)
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+    let mut col = column!(top, bottom).spacing(5);
+    // The backups do not contain these values, so players restored from one
+    // always pass these filters (see `TargetFilter::matches`)
+    if filter.class.is_some()
+        || !filter.guild.trim().is_empty()
+        || filter.max_age > 0
+    {
+        col = col.push(
+            text(
+                "Class, guild & age only filter players crawled since the \
+                 helper started. Players restored from a backup are always \
+                 shown",
+            )
+            .size(12),
+        );
+    }
+    col.into()
+}
+
+/// A clickable column header, that changes the sort order of the target list
+pub fn sort_header<'a>(
+    ident: AccountIdent,
+    filter: &TargetFilter,
+    underworld: bool,
+    label: &str,
+    column: SortColumn,
+    alignment: Horizontal,
+) -> Button<'a, Message> {
+    let indicator = match filter.sort {
+        Some(sort) if sort.column == column => match sort.descending {
+            true => " v",
+            false => " ^",
+        },
+        _ => "",
+    };
+    button(
+        text(format!("{label}{indicator}"))
+            .horizontal_alignment(alignment)
+            .width(Length::Fill),
+    )
+    .on_press(Message::PlayerSetFilter {
+        ident,
+        underworld,
+        filter: filter.with_sort(column),
+    })
+    .style(theme::Button::Text)
+    .padding(0)
+}
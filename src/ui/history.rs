@@ -0,0 +1,105 @@
+use iced::{
+    alignment::Horizontal,
+    theme,
+    widget::{
+        button, column, pick_list, row, scrollable, text, text_input,
+        vertical_space,
+    },
+    Alignment, Element, Length,
+};
+
+use crate::{
+    history::{HistoryEvent, HistoryFilter},
+    message::Message,
+    player::AccountInfo,
+};
+
+/// The amount of entries, that we display at once. Long running accounts can
+/// have tens of thousands of entries, which would make the UI very slow
+const MAX_DISPLAYED: usize = 500;
+
+pub fn view_history(player: &AccountInfo) -> Element<'_, Message> {
+    let history = &player.history;
+    let aid = player.ident;
+
+    let mut left_col = column!().align_items(Alignment::Center).spacing(10);
+
+    left_col = left_col.push(row!(
+        text("Events:").width(Length::FillPortion(1)),
+        text(history.entries.len())
+            .width(Length::FillPortion(1))
+            .horizontal_alignment(Horizontal::Right)
+    ));
+
+    let filter =
+        pick_list(HistoryFilter::ALL, Some(history.filter), move |nv| {
+            Message::HistorySetFilter {
+                ident: aid,
+                filter: nv,
+            }
+        })
+        .width(Length::FillPortion(1));
+    left_col = left_col.push(
+        row!(text("Show:").width(Length::FillPortion(1)), filter)
+            .align_items(Alignment::Center),
+    );
+
+    let search = text_input("Search", &history.search).on_input(move |nv| {
+        Message::HistorySetSearch {
+            ident: aid,
+            search: nv,
+        }
+    });
+    left_col = left_col.push(search);
+
+    left_col = left_col.push(
+        button("Export CSV").on_press(Message::HistoryExport { ident: aid }),
+    );
+
+    if let Some(status) = &history.export_status {
+        left_col = left_col.push(text(status));
+    }
+    left_col = left_col.push(vertical_space());
+
+    let name_bar = row!(
+        text("Time")
+            .width(Length::FillPortion(2))
+            .horizontal_alignment(Horizontal::Left),
+        text("Event")
+            .width(Length::FillPortion(6))
+            .horizontal_alignment(Horizontal::Left),
+    );
+
+    let mut entry_list = column!().spacing(5);
+    for entry in history.filtered().take(MAX_DISPLAYED) {
+        let time = text(entry.time.format("%Y-%m-%d %H:%M").to_string())
+            .width(Length::FillPortion(2));
+        let event = text(entry.event.describe()).width(Length::FillPortion(6));
+
+        let style = match &entry.event {
+            HistoryEvent::Fight { won: true, .. }
+            | HistoryEvent::Lure { won: true, .. } => theme::Button::Positive,
+            HistoryEvent::Fight { won: false, .. }
+            | HistoryEvent::Lure { won: false, .. }
+            | HistoryEvent::Error { .. } => theme::Button::Destructive,
+            _ => theme::Button::Secondary,
+        };
+        entry_list = entry_list.push(
+            button(row!(time, event).align_items(Alignment::Center))
+                .style(style)
+                .padding(5)
+                .width(Length::Fill),
+        );
+    }
+
+    let right_col = column!(name_bar, scrollable(entry_list)).spacing(10);
+
+    row!(
+        left_col.width(Length::Fixed(200.0)),
+        right_col.width(Length::Fill)
+    )
+    .padding(15)
+    .height(Length::Fill)
+    .align_items(Alignment::Start)
+    .into()
+}
@@ -0,0 +1,259 @@
+use iced::{
+    alignment::Horizontal,
+    theme,
+    widget::{
+        button, column, row, scrollable, text, text_input, vertical_space,
+        Image,
+    },
+    Alignment, Element, Length,
+};
+use num_format::ToFormattedString;
+use sf_api::gamestate::unlockables::EquipmentIdent;
+
+use super::{item_name, view_crawling};
+use crate::{
+    config::Config,
+    message::Message,
+    player::AccountInfo,
+    server::{CrawlingStatus, ServerInfo},
+    AttackTarget, ClassImages,
+};
+
+/// The amount of items & carriers, that we display at once. A fully crawled
+/// server has thousands of missing items, which would make the UI very slow
+const MAX_DISPLAYED: usize = 500;
+
+pub fn view_items<'a>(
+    server: &'a ServerInfo,
+    player: &'a AccountInfo,
+    config: &'a Config,
+    images: &'a ClassImages,
+) -> Element<'a, Message> {
+    let Some(si) = &player.scrapbook_info else {
+        return text("Player does not have a scrapbook").size(20).into();
+    };
+    let aid = player.ident;
+
+    let mut left_col = column!().align_items(Alignment::Center).spacing(10);
+
+    left_col = left_col.push(row!(
+        text("Missing Items:").width(Length::FillPortion(1)),
+        text(
+            si.missing_items
+                .len()
+                .to_formatted_string(&config.num_format)
+        )
+        .width(Length::FillPortion(1))
+        .horizontal_alignment(Horizontal::Right)
+    ));
+
+    left_col = left_col.push(row!(
+        text("Attackable:").width(Length::FillPortion(1)),
+        text(
+            si.missing_attackable
+                .to_formatted_string(&config.num_format)
+        )
+        .width(Length::FillPortion(1))
+        .horizontal_alignment(Horizontal::Right)
+    ));
+
+    left_col = left_col.push(vertical_space());
+    left_col = left_col.push(view_crawling(server, config));
+
+    let search = si.item_search.trim().to_lowercase();
+    let mut items = si
+        .missing_items
+        .iter()
+        .filter(|(item, _)| {
+            // The type & class are part of the search, so that whole groups
+            // can be found
+            let haystack =
+                format!("{:?} {:?} {}", item.typ, item.class, item_name(item));
+            search.is_empty() || haystack.to_lowercase().contains(&search)
+        })
+        .peekable();
+
+    let mut item_list = column!().spacing(5);
+    let mut last_group = None;
+    for (item, carriers) in items.by_ref().take(MAX_DISPLAYED) {
+        let group = (item.typ, item.class);
+        if last_group != Some(group) {
+            last_group = Some(group);
+            let class = match item.class {
+                Some(class) => format!("{class:?}"),
+                None => "All classes".to_string(),
+            };
+            item_list = item_list
+                .push(text(format!("{:?} - {class}", item.typ)).size(18));
+        }
+
+        let selected = si.selected_item.as_ref() == Some(item);
+        item_list = item_list.push(
+            button(row!(
+                text(item_name(item)).width(Length::FillPortion(3)),
+                text(carriers)
+                    .width(Length::FillPortion(1))
+                    .horizontal_alignment(Horizontal::Right)
+            ))
+            .on_press(Message::ItemSelected {
+                ident: aid,
+                item: *item,
+            })
+            .style(match selected {
+                true => theme::Button::Primary,
+                false => theme::Button::Secondary,
+            })
+            .padding(5)
+            .width(Length::Fill),
+        );
+    }
+    if items.peek().is_some() {
+        item_list = item_list.push(text(format!(
+            "Only the first {MAX_DISPLAYED} items are shown. Search to find \
+             the others"
+        )));
+    }
+
+    let search = text_input("Search", &si.item_search).on_input(move |nv| {
+        Message::ItemSearch {
+            ident: aid,
+            search: nv,
+        }
+    });
+
+    let item_col = column!(
+        search,
+        row!(
+            text("Item").width(Length::FillPortion(3)),
+            text("Carriers")
+                .width(Length::FillPortion(1))
+                .horizontal_alignment(Horizontal::Right)
+        ),
+        scrollable(item_list)
+    )
+    .spacing(10);
+
+    let mut carrier_list = column!().spacing(10);
+    match &si.selected_item {
+        Some(item) => {
+            let mut carriers: Vec<_> =
+                attackable_carriers(server, player, item).collect();
+            carriers.sort_by(|a, b| {
+                b.missing
+                    .cmp(&a.missing)
+                    .then(a.info.stats.cmp(&b.info.stats))
+                    .then(a.info.level.cmp(&b.info.level))
+            });
+            if carriers.is_empty() {
+                carrier_list = carrier_list
+                    .push(text("Nobody, that we can attack, has this item"));
+            }
+            if carriers.len() > MAX_DISPLAYED {
+                carrier_list = carrier_list.push(text(format!(
+                    "Only the {MAX_DISPLAYED} best of {} carriers are shown",
+                    carriers.len()
+                )));
+            }
+            for target in carriers.into_iter().take(MAX_DISPLAYED) {
+                let mut target_ident = row!()
+                    .align_items(Alignment::Start)
+                    .spacing(5)
+                    .width(Length::FillPortion(15));
+
+                if let Some(class) = target.info.class {
+                    if config.show_class_icons {
+                        let img = Image::new(images.get_handle(class))
+                            .width(Length::FillPortion(1))
+                            .content_fit(iced::ContentFit::ScaleDown);
+                        target_ident = target_ident.push(img);
+                    }
+                }
+                target_ident = target_ident.push(
+                    text(&target.info.name)
+                        .width(Length::FillPortion(15))
+                        .horizontal_alignment(Horizontal::Left),
+                );
+
+                carrier_list = carrier_list.push(row!(
+                    text(target.missing)
+                        .width(Length::FillPortion(5))
+                        .horizontal_alignment(Horizontal::Center),
+                    text(target.info.level)
+                        .width(Length::FillPortion(5))
+                        .horizontal_alignment(Horizontal::Center),
+                    target_ident,
+                    column!(button("Attack").on_press(Message::PlayerAttack {
+                        ident: aid,
+                        target,
+                    }))
+                    .align_items(Alignment::Center)
+                    .width(Length::FillPortion(5)),
+                ));
+            }
+        }
+        None => {
+            carrier_list = carrier_list
+                .push(text("Select an item to see, who carries it"));
+        }
+    }
+
+    let carrier_col = column!(
+        row!(
+            text("Missing")
+                .width(Length::FillPortion(5))
+                .horizontal_alignment(Horizontal::Center),
+            text("Level")
+                .width(Length::FillPortion(5))
+                .horizontal_alignment(Horizontal::Center),
+            text("Name")
+                .width(Length::FillPortion(15))
+                .horizontal_alignment(Horizontal::Left),
+            text("").width(Length::FillPortion(5)),
+        ),
+        scrollable(carrier_list)
+    )
+    .spacing(10);
+
+    let right_col = row!(
+        item_col.width(Length::FillPortion(2)),
+        carrier_col.width(Length::FillPortion(3))
+    )
+    .spacing(15);
+
+    row!(
+        left_col.width(Length::Fixed(200.0)),
+        right_col.width(Length::Fill)
+    )
+    .padding(15)
+    .height(Length::Fill)
+    .align_items(Alignment::Start)
+    .into()
+}
+
+/// All players, that have the item equipped and are not filtered out by the
+/// current scrapbook settings
+fn attackable_carriers<'a>(
+    server: &'a ServerInfo,
+    player: &'a AccountInfo,
+    item: &EquipmentIdent,
+) -> impl Iterator<Item = AttackTarget> + 'a {
+    let (carriers, player_info) = match &server.crawling {
+        CrawlingStatus::Crawling {
+            equipment,
+            player_info,
+            ..
+        } => (equipment.get(item), Some(player_info)),
+        _ => (None, None),
+    };
+    let counts = player.scrapbook_info.as_ref().map(|a| &a.per_player_counts);
+
+    carriers.into_iter().flatten().filter_map(move |uid| {
+        let missing = *counts?.get(uid)?;
+        let info = player_info?.get(uid)?;
+        Some(AttackTarget {
+            missing,
+            score: missing as f32,
+            info: info.clone(),
+        })
+    })
+}
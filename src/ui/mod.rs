@@ -6,28 +6,39 @@ use iced::{
     theme,
     widget::{
         self, button, checkbox, column, container, horizontal_space, pick_list,
-        progress_bar, row, text, Button,
+        progress_bar, row, text, text_input, Button,
     },
     Alignment, Element, Length,
 };
 use iced_aw::{number_input, widgets::DropDown};
 use num_format::ToFormattedString;
 use options::view_options;
+use sf_api::gamestate::{items::EquipmentSlot, unlockables::EquipmentIdent};
 
-use self::{scrapbook::view_scrapbook, underworld::view_underworld};
+use self::{
+    history::view_history, items::view_items, scrapbook::view_scrapbook,
+    underworld::view_underworld,
+};
 use crate::{
-    config::{AvailableTheme, Config},
+    config::{AvailableTheme, Config, LureRules},
     crawler::CrawlingOrder,
     get_server_code,
     message::Message,
     player::{AccountInfo, AccountStatus},
+    profile::{self, ProfileChoice},
     server::{CrawlingStatus, ServerInfo},
-    top_bar, AccountIdent, AccountPage, ActionSelection, Helper, View,
+    top_bar, vault, AccountIdent, AccountPage, ActionSelection, Helper, View,
 };
 
+mod filter;
+mod history;
+mod items;
 mod options;
+mod progress;
 mod scrapbook;
+mod target;
 pub mod underworld;
+mod watchlist;
 
 impl Helper {
     pub fn view_current_page(&self) -> Element<Message> {
@@ -40,6 +51,8 @@ impl Helper {
                 self.view_overview(selected, action)
             }
             View::Settings => self.view_settings(),
+            View::Watchlist => self.view_watchlist(),
+            View::Unlock => self.view_unlock(),
         };
         let main_part = container(view).width(Length::Fill).center_x();
         let mut res = column!();
@@ -67,6 +80,70 @@ impl Helper {
 
             res = res.push(update_msg);
         }
+        if let Some(error) = &self.config_error {
+            let error_msg = row!(
+                horizontal_space(),
+                text(&error.message).size(16),
+                horizontal_space(),
+                button("Dismiss")
+                    .on_press(Message::DismissConfigError)
+                    .style(theme::Button::Destructive),
+            )
+            .align_items(Alignment::Center)
+            .spacing(10)
+            .width(Length::Fill)
+            .padding(15);
+
+            res = res.push(error_msg);
+        }
+        if !self.alerts.is_empty()
+            && !matches!(self.current_view, View::Watchlist)
+        {
+            let alert_msg = row!(
+                horizontal_space(),
+                text(format!(
+                    "{} watched player(s) equipped missing items!",
+                    self.alerts.len()
+                ))
+                .size(20),
+                button("View").on_press(Message::ViewWatchlist),
+                horizontal_space(),
+                button("Dismiss")
+                    .on_press(Message::WatchlistDismissAlerts)
+                    .style(theme::Button::Destructive),
+            )
+            .align_items(Alignment::Center)
+            .spacing(10)
+            .width(Length::Fill)
+            .padding(15);
+
+            res = res.push(alert_msg);
+        }
+        if !self.souls_alerts.is_empty() {
+            let names: Vec<_> = self
+                .souls_alerts
+                .iter()
+                .map(|a| titlecase::titlecase(a))
+                .collect();
+            let alert_msg = row!(
+                horizontal_space(),
+                text(format!(
+                    "The souls of {} are almost full!",
+                    names.join(", ")
+                ))
+                .size(20),
+                horizontal_space(),
+                button("Dismiss")
+                    .on_press(Message::SoulsDismissAlerts)
+                    .style(theme::Button::Destructive),
+            )
+            .align_items(Alignment::Center)
+            .spacing(10)
+            .width(Length::Fill)
+            .padding(15);
+
+            res = res.push(alert_msg);
+        }
         res.push(main_part).into()
     }
 
@@ -103,6 +180,8 @@ impl Helper {
                 .size(20),
             selection(AccountPage::Scrapbook),
             selection(AccountPage::Underworld),
+            selection(AccountPage::Items),
+            selection(AccountPage::History),
             selection(AccountPage::Options),
             button(text("Logout"))
                 .on_press(Message::RemoveAccount {
@@ -123,6 +202,10 @@ impl Helper {
             AccountPage::Underworld => view_underworld(
                 server, player, &self.config, &self.class_images,
             ),
+            AccountPage::Items => {
+                view_items(server, player, &self.config, &self.class_images)
+            }
+            AccountPage::History => view_history(player),
             AccountPage::Options => view_options(player, server, &self.config),
         };
 
@@ -217,14 +300,65 @@ impl Helper {
         .width(Length::Fill)
         .align_items(Alignment::Center);
 
+        let verify_targets = checkbox(
+            "Verify targets before auto-battle", self.config.verify_targets,
+        )
+        .on_toggle(Message::SetVerifyTargets);
+
+        let verify_window = number_input(
+            self.config.verify_window,
+            24 * 60,
+            Message::SetVerifyWindow,
+        );
+
+        let verify_window = row!(
+            "Verify if older than (min):",
+            horizontal_space(),
+            verify_window
+        )
+        .width(Length::Fill)
+        .align_items(Alignment::Center);
+
+        let lure_cooldown = number_input(
+            self.config.lure_cooldown,
+            24 * 7,
+            Message::SetLureCooldown,
+        );
+
+        let lure_cooldown =
+            row!("Lure cooldown (h):", horizontal_space(), lure_cooldown)
+                .width(Length::Fill)
+                .align_items(Alignment::Center);
+
+        let souls_alert =
+            number_input(self.config.souls_alert, 100, Message::SetSoulsAlert);
+
+        let souls_alert =
+            row!("Souls alert (%, 0 = off):", horizontal_space(), souls_alert)
+                .width(Length::Fill)
+                .align_items(Alignment::Center);
+
         let settings_column = column!(
             theme_row, auto_fetch_hof, auto_poll, max_threads, start_threads,
-            blacklist_threshold, crawling_restrict, show_class_icons
+            blacklist_threshold, verify_targets, verify_window, lure_cooldown,
+            souls_alert, crawling_restrict, show_class_icons
         )
         .width(Length::Fixed(300.0))
         .spacing(20);
 
-        column!(top_row, settings_column)
+        let side_column = column!(
+            view_lure_rules(&self.config),
+            view_api(&self.config),
+            self.view_vault(),
+            self.view_profiles()
+        )
+        .spacing(40);
+
+        let settings = row!(settings_column, side_column)
+            .spacing(50)
+            .align_items(Alignment::Start);
+
+        column!(top_row, settings)
             .spacing(20)
             .height(Length::Fill)
             .width(Length::Fill)
@@ -232,13 +366,108 @@ impl Helper {
             .into()
     }
 
+    fn view_unlock(&self) -> Element<Message> {
+        let password =
+            text_input("Master password", &self.login_state.vault_password)
+                .secure(true)
+                .on_input(Message::VaultPasswordChanged)
+                .on_submit(Message::VaultUnlock)
+                .width(Length::Fixed(300.0));
+
+        let mut res = column!(
+            text("Unlock credentials").size(20),
+            password,
+            button("Unlock").on_press(Message::VaultUnlock).padding(4)
+        )
+        .spacing(20)
+        .padding(50)
+        .align_items(Alignment::Center);
+
+        if let Some(error) = &self.login_state.error {
+            res = res.push(text(error));
+        }
+        res.into()
+    }
+
+    fn view_vault(&self) -> Element<Message> {
+        let title = text("Credentials").size(18);
+        let mut res = if vault::is_unlocked() {
+            let info =
+                text("Passwords are stored in the encrypted vault").size(12);
+            let disable = button("Store in config")
+                .on_press(Message::VaultDisable)
+                .style(theme::Button::Destructive);
+            column!(title, info, disable)
+        } else {
+            let password =
+                text_input("Master password", &self.login_state.vault_password)
+                    .secure(true)
+                    .on_input(Message::VaultPasswordChanged)
+                    .on_submit(Message::VaultEnable);
+            let mut enable = button("Encrypt");
+            if !self.login_state.vault_password.is_empty() {
+                enable = enable.on_press(Message::VaultEnable);
+            }
+            let row = row!(password, enable)
+                .spacing(10)
+                .align_items(Alignment::Center);
+            column!(title, row)
+        };
+
+        if let Some(error) = &self.login_state.vault_error {
+            res = res.push(text(error).size(12));
+        }
+        res.width(Length::Fixed(300.0)).spacing(20).into()
+    }
+
+    fn view_profiles(&self) -> Element<Message> {
+        let current = ProfileChoice(profile::active().map(|a| a.to_string()));
+        let mut choices = vec![ProfileChoice(None)];
+        choices.extend(
+            profile::list().into_iter().map(|a| ProfileChoice(Some(a))),
+        );
+
+        let switcher =
+            pick_list(choices, Some(current), Message::ProfileSwitch)
+                .width(Length::Fixed(150.0));
+        let switcher = row!("Active:", horizontal_space(), switcher)
+            .width(Length::Fill)
+            .align_items(Alignment::Center);
+
+        let name = text_input("New profile", &self.new_profile)
+            .on_input(Message::ProfileNameChanged)
+            .on_submit(Message::ProfileCreate);
+        let mut create = button("Create");
+        if profile::is_valid_name(&self.new_profile) {
+            create = create.on_press(Message::ProfileCreate);
+        }
+        let create = row!(name, create)
+            .spacing(10)
+            .align_items(Alignment::Center);
+
+        column!(text("Profile").size(18), switcher, create)
+            .width(Length::Fixed(300.0))
+            .spacing(20)
+            .into()
+    }
+
     fn view_overview(
         &self,
         selected: &HashSet<AccountIdent>,
         currrent_action: &Option<ActionSelection>,
     ) -> Element<Message> {
-        let top_bar =
-            top_bar(text("Overview").size(20).into(), Some(Message::ViewLogin));
+        let title = row!(
+            text("Overview").size(20),
+            button(text(format!(
+                "Watchlist ({})",
+                self.config.watchlist.len()
+            )))
+            .padding(4)
+            .on_press(Message::ViewWatchlist)
+        )
+        .spacing(15)
+        .align_items(Alignment::Center);
+        let top_bar = top_bar(title.into(), Some(Message::ViewLogin));
 
         let mut accounts = column!()
             .padding(20)
@@ -581,6 +810,108 @@ fn remaining_minutes(time: DateTime<Local>) -> String {
     format!("{mins}:{secs:02}")
 }
 
+/// A short, human readable name for an item. The game data does not contain
+/// the actual item names, so this is just the model & color
+fn item_name(item: &EquipmentIdent) -> String {
+    format!("Model {} (Color {})", item.model_id, item.color)
+}
+
+const LURE_SLOTS: [EquipmentSlot; 10] = [
+    EquipmentSlot::Hat,
+    EquipmentSlot::BreastPlate,
+    EquipmentSlot::Gloves,
+    EquipmentSlot::FootWear,
+    EquipmentSlot::Amulet,
+    EquipmentSlot::Belt,
+    EquipmentSlot::Ring,
+    EquipmentSlot::Talisman,
+    EquipmentSlot::Weapon,
+    EquipmentSlot::Shield,
+];
+
+/// The rules, that decide which crawled players are lure targets
+fn view_lure_rules(config: &Config) -> Element<Message> {
+    let rules = &config.lure_rules;
+
+    let max_items = number_input(rules.max_items, 10, move |nv| {
+        Message::SetLureRules(LureRules {
+            max_items: nv,
+            ..rules.clone()
+        })
+    });
+    let max_items = row!("Max items:", horizontal_space(), max_items)
+        .width(Length::Fill)
+        .align_items(Alignment::Center);
+
+    let min_level = number_input(rules.min_level, 9999, move |nv| {
+        Message::SetLureRules(LureRules {
+            min_level: nv,
+            ..rules.clone()
+        })
+    });
+    let min_level = row!("Min level:", horizontal_space(), min_level)
+        .width(Length::Fill)
+        .align_items(Alignment::Center);
+
+    let mut col = column!(
+        text("Lure Targets").size(18),
+        max_items,
+        min_level,
+        text("Slots, that have to be empty:")
+    )
+    .width(Length::Fixed(300.0))
+    .spacing(20);
+
+    for slots in LURE_SLOTS.chunks(2) {
+        let mut slot_row = row!().spacing(10);
+        for slot in slots {
+            let slot = *slot;
+            let empty = rules.empty_slots.contains(&slot);
+            slot_row = slot_row.push(
+                checkbox(format!("{slot:?}"), empty)
+                    .on_toggle(move |nv| {
+                        let mut rules = rules.clone();
+                        rules.empty_slots.retain(|a| *a != slot);
+                        if nv {
+                            rules.empty_slots.push(slot);
+                        }
+                        Message::SetLureRules(rules)
+                    })
+                    .width(Length::FillPortion(1)),
+            );
+        }
+        col = col.push(slot_row);
+    }
+    col.into()
+}
+
+fn view_api(config: &Config) -> Element<Message> {
+    let api = &config.api;
+
+    let enabled = checkbox("Enable local API", api.enabled)
+        .on_toggle(Message::SetApiEnabled);
+    let metrics = checkbox("Expose /metrics", api.metrics)
+        .on_toggle(Message::SetApiMetrics);
+
+    let port = number_input(api.port, u16::MAX, Message::SetApiPort);
+    let port = row!("Port:", horizontal_space(), port)
+        .width(Length::Fill)
+        .align_items(Alignment::Center);
+
+    let token = row!(
+        text(format!("Token: {}", api.token)).size(12),
+        horizontal_space(),
+        button("New").on_press(Message::ApiNewToken)
+    )
+    .width(Length::Fill)
+    .align_items(Alignment::Center);
+
+    column!(text("Local API").size(18), enabled, metrics, port, token)
+        .width(Length::Fixed(300.0))
+        .spacing(20)
+        .into()
+}
+
 fn center(t: text::Text) -> text::Text {
     t.horizontal_alignment(Horizontal::Center)
 }
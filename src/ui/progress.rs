@@ -0,0 +1,270 @@
+use chrono::{Local, NaiveDate};
+use iced::{
+    mouse,
+    widget::{
+        canvas::{self, Frame, LineDash, Path, Stroke, Text},
+        column, pick_list, row, text,
+    },
+    Alignment, Color, Element, Length, Point, Rectangle, Renderer, Size, Theme,
+};
+use num_format::ToFormattedString;
+
+use crate::{
+    config::Config,
+    message::Message,
+    player::{AccountInfo, ScrapbookInfo},
+    progress::{ChartMode, ScrapbookProgress},
+};
+
+pub fn view_progress<'a>(
+    player: &'a AccountInfo,
+    si: &'a ScrapbookInfo,
+    config: &'a Config,
+) -> Element<'a, Message> {
+    let progress = &player.progress;
+    let aid = player.ident;
+
+    let projection = progress.projected_completion(si.missing_available);
+
+    let mode = pick_list(
+        [ChartMode::Progress, ChartMode::PerDay],
+        Some(progress.chart_mode),
+        move |nv| Message::ProgressChartMode {
+            ident: aid,
+            mode: nv,
+        },
+    );
+
+    let avg = progress
+        .avg_per_day(14)
+        .map(|a| format!("{a:.1}"))
+        .unwrap_or("???".to_string());
+
+    let completion = match projection {
+        Some((date, _)) => {
+            let days = (date - Local::now().date_naive()).num_days().max(0);
+            format!("~{days} days ({})", date.format("%Y-%m-%d"))
+        }
+        None => "???".to_string(),
+    };
+
+    let info = row!(
+        mode,
+        text(format!(
+            "Available: {}",
+            si.missing_available.to_formatted_string(&config.num_format)
+        )),
+        text(format!("Avg/Day: {avg}")),
+        text(format!("Completion: {completion}")),
+    )
+    .spacing(15)
+    .align_items(Alignment::Center);
+
+    let chart = canvas::Canvas::new(ProgressChart {
+        progress,
+        projection,
+    })
+    .width(Length::Fill)
+    .height(Length::Fixed(150.0));
+
+    column!(info, chart).spacing(10).into()
+}
+
+struct ProgressChart<'a> {
+    progress: &'a ScrapbookProgress,
+    projection: Option<(NaiveDate, usize)>,
+}
+
+const CHART_PADDING: f32 = 30.0;
+
+impl<'a> canvas::Program<Message> for ProgressChart<'a> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let palette = theme.palette();
+
+        let width = frame.width() - CHART_PADDING * 2.0;
+        let height = frame.height() - CHART_PADDING * 2.0;
+
+        let axis = Path::new(|b| {
+            b.move_to(Point::new(CHART_PADDING, CHART_PADDING));
+            b.line_to(Point::new(CHART_PADDING, CHART_PADDING + height));
+            b.line_to(Point::new(
+                CHART_PADDING + width,
+                CHART_PADDING + height,
+            ));
+        });
+        frame.stroke(
+            &axis,
+            Stroke::default().with_color(palette.text).with_width(1.0),
+        );
+
+        let Some((first, _)) = self.progress.days.first_key_value() else {
+            frame.fill_text(Text {
+                content: "No data yet".to_string(),
+                position: frame.center(),
+                color: palette.text,
+                ..Default::default()
+            });
+            return vec![frame.into_geometry()];
+        };
+        let first = *first;
+
+        let label = |frame: &mut Frame, content: String, position: Point| {
+            frame.fill_text(Text {
+                content,
+                position,
+                color: palette.text,
+                size: 11.0.into(),
+                ..Default::default()
+            });
+        };
+
+        match self.progress.chart_mode {
+            ChartMode::Progress => {
+                let last = self
+                    .projection
+                    .map(|a| a.0)
+                    .or(self.progress.days.last_key_value().map(|a| *a.0))
+                    .unwrap_or(first);
+                let max_items = self
+                    .projection
+                    .map(|a| a.1)
+                    .into_iter()
+                    .chain(self.progress.days.values().map(|a| a.items))
+                    .max()
+                    .unwrap_or(1)
+                    .max(1);
+                let min_items = self
+                    .progress
+                    .days
+                    .values()
+                    .map(|a| a.items)
+                    .min()
+                    .unwrap_or(0);
+                let total_days = (last - first).num_days().max(1) as f32;
+                let item_range = (max_items - min_items).max(1) as f32;
+
+                let to_point = |date: NaiveDate, items: usize| {
+                    let x = (date - first).num_days() as f32 / total_days;
+                    let y = (items - min_items) as f32 / item_range;
+                    Point::new(
+                        CHART_PADDING + x * width,
+                        CHART_PADDING + height - y * height,
+                    )
+                };
+
+                let line = Path::new(|b| {
+                    for (pos, (date, snapshot)) in
+                        self.progress.days.iter().enumerate()
+                    {
+                        let point = to_point(*date, snapshot.items);
+                        if pos == 0 {
+                            b.move_to(point);
+                        } else {
+                            b.line_to(point);
+                        }
+                        b.circle(point, 1.5);
+                        b.move_to(point);
+                    }
+                });
+                frame.stroke(
+                    &line,
+                    Stroke::default()
+                        .with_color(palette.primary)
+                        .with_width(2.0),
+                );
+
+                if let (Some((date, items)), Some((last_date, last))) =
+                    (self.projection, self.progress.days.last_key_value())
+                {
+                    let projection = Path::line(
+                        to_point(*last_date, last.items),
+                        to_point(date, items),
+                    );
+                    frame.stroke(
+                        &projection,
+                        Stroke {
+                            line_dash: LineDash {
+                                segments: &[4.0, 4.0],
+                                offset: 0,
+                            },
+                            ..Stroke::default()
+                                .with_color(palette.success)
+                                .with_width(1.0)
+                        },
+                    );
+                }
+
+                label(
+                    &mut frame,
+                    max_items.to_string(),
+                    Point::new(0.0, CHART_PADDING - 15.0),
+                );
+                label(
+                    &mut frame,
+                    min_items.to_string(),
+                    Point::new(0.0, CHART_PADDING + height + 2.0),
+                );
+                label(
+                    &mut frame,
+                    first.format("%Y-%m-%d").to_string(),
+                    Point::new(CHART_PADDING, CHART_PADDING + height + 5.0),
+                );
+                label(
+                    &mut frame,
+                    last.format("%Y-%m-%d").to_string(),
+                    Point::new(
+                        CHART_PADDING + width - 60.0,
+                        CHART_PADDING + height + 5.0,
+                    ),
+                );
+            }
+            ChartMode::PerDay => {
+                let per_day = self.progress.items_per_day();
+                let max = per_day.iter().map(|a| a.1).fold(1.0, f32::max);
+                let bar_width = width / per_day.len().max(1) as f32;
+
+                for (pos, (_, items)) in per_day.iter().enumerate() {
+                    let bar_height = *items / max * height;
+                    frame.fill_rectangle(
+                        Point::new(
+                            CHART_PADDING + pos as f32 * bar_width + 1.0,
+                            CHART_PADDING + height - bar_height,
+                        ),
+                        Size::new((bar_width - 2.0).max(1.0), bar_height),
+                        Color {
+                            a: 0.8,
+                            ..palette.primary
+                        },
+                    );
+                }
+                label(
+                    &mut frame,
+                    ((max * 10.0).round() / 10.0).to_string(),
+                    Point::new(0.0, CHART_PADDING - 15.0),
+                );
+                if let Some((date, _)) = per_day.last() {
+                    label(
+                        &mut frame,
+                        date.format("%Y-%m-%d").to_string(),
+                        Point::new(
+                            CHART_PADDING + width - 60.0,
+                            CHART_PADDING + height + 5.0,
+                        ),
+                    );
+                }
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
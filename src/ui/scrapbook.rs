@@ -5,21 +5,28 @@ use iced::{
     alignment::Horizontal,
     theme,
     widget::{
-        button, checkbox, column, horizontal_space, row, scrollable, text,
-        vertical_space, Image,
+        button, checkbox, column, horizontal_space, pick_list, row, scrollable,
+        text, text_input, vertical_space, Image,
     },
     Alignment, Element, Length,
 };
 use iced_aw::number_input;
 use num_format::ToFormattedString;
 
-use super::{remaining_minutes, view_crawling};
+use super::{
+    filter::{sort_header, view_filter},
+    progress::view_progress,
+    remaining_minutes,
+    target::view_target_detail,
+    view_crawling,
+};
 use crate::{
-    config::Config,
+    config::{Config, EpicMode, SortColumn, TargetScoring},
     message::Message,
-    player::{AccountInfo, AccountStatus},
+    player::{AccountInfo, AccountStatus, ScrapbookInfo},
+    scripting::SCRIPT_VARIABLES,
     server::ServerInfo,
-    ClassImages,
+    AttackTarget, ClassImages,
 };
 
 pub fn view_scrapbook<'a>(
@@ -137,6 +144,68 @@ pub fn view_scrapbook<'a>(
             .size(20),
     );
 
+    let scoring = pick_list(TargetScoring::ALL, Some(si.scoring), move |nv| {
+        Message::PlayerSetScoring {
+            ident: aid,
+            scoring: nv,
+        }
+    });
+    left_col = left_col.push(
+        row!(text("Scoring:"), horizontal_space(), scoring)
+            .align_items(Alignment::Center),
+    );
+
+    if si.scoring == TargetScoring::Script {
+        let script = text_input("missing * 2 - level / 100", &si.score_script)
+            .on_input(move |nv| Message::PlayerEditScript {
+                ident: aid,
+                script: nv,
+            })
+            .on_submit(Message::PlayerApplyScript { ident: aid });
+        let apply =
+            button("Apply").on_press(Message::PlayerApplyScript { ident: aid });
+        left_col = left_col.push(
+            row!(script, apply)
+                .spacing(5)
+                .align_items(Alignment::Center),
+        );
+        left_col = left_col
+            .push(text(format!("Variables: {SCRIPT_VARIABLES}")).size(12));
+        if let Some(error) = &si.script_error {
+            left_col = left_col.push(text(error).size(12).style(
+                theme::Text::Color(iced::Color::from_rgb(0.9, 0.2, 0.2)),
+            ));
+        }
+    }
+
+    let epic_mode = si.epic_mode;
+    left_col = left_col.push(
+        checkbox("Count Epics", epic_mode.enabled)
+            .on_toggle(move |nv| Message::PlayerSetEpicMode {
+                ident: aid,
+                mode: EpicMode {
+                    enabled: nv,
+                    ..epic_mode
+                },
+            })
+            .size(20),
+    );
+    if epic_mode.enabled {
+        let weight = number_input(epic_mode.weight, 10, move |nv| {
+            Message::PlayerSetEpicMode {
+                ident: aid,
+                mode: EpicMode {
+                    weight: nv.max(1),
+                    ..epic_mode
+                },
+            }
+        });
+        left_col = left_col.push(
+            row!(text("Epic Weight:"), horizontal_space(), weight)
+                .align_items(Alignment::Center),
+        );
+    }
+
     left_col = left_col.push(button("Copy Optimal Battle Order").on_press(
         Message::CopyBattleOrder {
             ident: player.ident,
@@ -168,28 +237,55 @@ pub fn view_scrapbook<'a>(
 
     left_col = left_col.push(view_crawling(server, config));
 
+    let header = |label, column, alignment| {
+        sort_header(aid, &si.filter_draft, false, label, column, alignment)
+    };
     let mut name_bar = column!();
     name_bar = name_bar.push(row!(
         text("")
             .width(Length::FillPortion(5))
             .horizontal_alignment(Horizontal::Center),
-        text("Missing")
-            .width(Length::FillPortion(5))
-            .horizontal_alignment(Horizontal::Center),
-        text("Level")
-            .width(Length::FillPortion(5))
-            .horizontal_alignment(Horizontal::Center),
-        text("Attributes")
-            .width(Length::FillPortion(5))
-            .horizontal_alignment(Horizontal::Center),
-        text("Name")
-            .width(Length::FillPortion(15))
-            .horizontal_alignment(Horizontal::Left),
+        header(
+            match si.scoring {
+                TargetScoring::Missing => "Missing",
+                TargetScoring::Rarity | TargetScoring::Script => "Score",
+            },
+            SortColumn::Missing,
+            Horizontal::Center
+        )
+        .width(Length::FillPortion(5)),
+        header("Level", SortColumn::Level, Horizontal::Center)
+            .width(Length::FillPortion(5)),
+        header("Attributes", SortColumn::Attributes, Horizontal::Center)
+            .width(Length::FillPortion(5)),
+        header("Name", SortColumn::Name, Horizontal::Left)
+            .width(Length::FillPortion(15)),
     ));
     let name_bar = scrollable(name_bar);
 
+    // Sorting is only done for display purposes. Auto-battle always uses the
+    // order, that the scoring determined
+    let mut targets: Vec<_> = si.best.iter().collect();
+    if let Some(sort) = si.filter.sort {
+        targets.sort_by(|a, b| {
+            let ord = match sort.column {
+                SortColumn::Missing => a.score.total_cmp(&b.score),
+                SortColumn::Level => a.info.level.cmp(&b.info.level),
+                SortColumn::Attributes => a.info.stats.cmp(&b.info.stats),
+                SortColumn::Items => {
+                    a.info.equipment.len().cmp(&b.info.equipment.len())
+                }
+                SortColumn::Name => a.info.name.cmp(&b.info.name),
+            };
+            match sort.descending {
+                true => ord.reverse(),
+                false => ord,
+            }
+        });
+    }
+
     let mut target_list = column!().spacing(10);
-    for v in &si.best {
+    for v in targets {
         let mut target_ident = row!()
             .align_items(Alignment::Start)
             .spacing(5)
@@ -204,9 +300,17 @@ pub fn view_scrapbook<'a>(
             }
         }
         target_ident = target_ident.push(
-            text(&v.info.name)
-                .width(Length::FillPortion(15))
-                .horizontal_alignment(Horizontal::Left),
+            button(text(&v.info.name))
+                .on_press(Message::TargetSelected {
+                    ident: aid,
+                    uid: Some(v.info.uid),
+                })
+                .style(match si.selected_target == Some(v.info.uid) {
+                    true => theme::Button::Primary,
+                    false => theme::Button::Text,
+                })
+                .padding(0)
+                .width(Length::FillPortion(15)),
         );
 
         target_list = target_list.push(row!(
@@ -216,9 +320,14 @@ pub fn view_scrapbook<'a>(
             }))
             .align_items(Alignment::Center)
             .width(Length::FillPortion(5)),
-            text(v.missing)
-                .width(Length::FillPortion(5))
-                .horizontal_alignment(Horizontal::Center),
+            text(match si.scoring {
+                TargetScoring::Missing => missing_label(si, v),
+                TargetScoring::Rarity | TargetScoring::Script => {
+                    format!("{:.2} ({})", v.score, missing_label(si, v))
+                }
+            })
+            .width(Length::FillPortion(5))
+            .horizontal_alignment(Horizontal::Center),
             text(v.info.level)
                 .width(Length::FillPortion(5))
                 .horizontal_alignment(Horizontal::Center),
@@ -234,14 +343,41 @@ pub fn view_scrapbook<'a>(
         ));
     }
     let target_list = scrollable(target_list);
-    let right_col = column!(name_bar, target_list).spacing(10);
+    let right_col = column!(
+        view_progress(player, si, config),
+        view_filter(aid, &si.filter_draft, false),
+        name_bar,
+        target_list
+    )
+    .spacing(10);
 
-    row!(
+    let mut page = row!(
         left_col.width(Length::Fixed(200.0)),
-        right_col.width(Length::Fill)
+        right_col.width(Length::FillPortion(3))
     )
-    .padding(15)
-    .height(Length::Fill)
-    .align_items(Alignment::Start)
-    .into()
+    .spacing(15);
+
+    if let Some(uid) = si.selected_target {
+        page = page.push(
+            column!(view_target_detail(
+                server, player, si, uid, config, images
+            ))
+            .width(Length::FillPortion(2)),
+        );
+    }
+
+    page.padding(15)
+        .height(Length::Fill)
+        .align_items(Alignment::Start)
+        .into()
+}
+
+/// The amount of missing items of the target, with epics shown separately, if
+/// they are counted
+fn missing_label(si: &ScrapbookInfo, target: &AttackTarget) -> String {
+    let (normal, epics) = target.info.missing_split(&si.scrapbook.items);
+    if !si.epic_mode.enabled || epics == 0 {
+        return target.missing.to_string();
+    }
+    format!("{normal} + {epics} epic")
 }
@@ -0,0 +1,204 @@
+use chrono::Utc;
+use iced::{
+    alignment::Horizontal,
+    theme,
+    widget::{button, column, horizontal_space, row, scrollable, text, Image},
+    Alignment, Element, Length,
+};
+use num_format::ToFormattedString;
+
+use super::item_name;
+use crate::{
+    config::Config,
+    history::HistoryEvent,
+    message::Message,
+    player::{AccountInfo, ScrapbookInfo},
+    server::{CrawlingStatus, ServerInfo},
+    ClassImages,
+};
+
+/// The amount of past fights against the target, that we show
+const MAX_FIGHTS: usize = 10;
+
+/// The detail pane of a single scrapbook target
+pub fn view_target_detail<'a>(
+    server: &'a ServerInfo,
+    player: &'a AccountInfo,
+    si: &'a ScrapbookInfo,
+    uid: u32,
+    config: &'a Config,
+    images: &'a ClassImages,
+) -> Element<'a, Message> {
+    let aid = player.ident;
+
+    let close = button("Close")
+        .on_press(Message::TargetSelected {
+            ident: aid,
+            uid: None,
+        })
+        .style(theme::Button::Destructive)
+        .padding(4);
+
+    let CrawlingStatus::Crawling { player_info, .. } = &server.crawling else {
+        return column!(close, text("No crawling data available"))
+            .spacing(10)
+            .into();
+    };
+    let Some(info) = player_info.get(&uid) else {
+        return column!(close, text("Player is no longer known"))
+            .spacing(10)
+            .into();
+    };
+
+    let mut title = row!().spacing(10).align_items(Alignment::Center);
+    if let Some(class) = info.class {
+        if config.show_class_icons {
+            title = title.push(
+                Image::new(images.get_handle(class))
+                    .width(Length::Fixed(20.0))
+                    .content_fit(iced::ContentFit::ScaleDown),
+            );
+        }
+    }
+    title = title.push(text(&info.name).size(20));
+
+    let mut col = column!(row!(title, horizontal_space(), close))
+        .spacing(10)
+        .width(Length::Fill);
+
+    let info_row = |label: &'a str, value: String| {
+        row!(
+            text(label).width(Length::FillPortion(1)),
+            text(value)
+                .width(Length::FillPortion(1))
+                .horizontal_alignment(Horizontal::Right)
+        )
+    };
+
+    col = col.push(info_row("Level:", info.level.to_string()));
+    col = col.push(info_row(
+        "Class:",
+        info.class
+            .map(|a| format!("{a:?}"))
+            .unwrap_or("???".to_string()),
+    ));
+    col = col.push(info_row(
+        "Attributes:",
+        info.stats
+            .map(|a| a.to_formatted_string(&config.num_format))
+            .unwrap_or("???".to_string()),
+    ));
+    col = col.push(info_row(
+        "Guild:",
+        info.guild.clone().unwrap_or("-".to_string()),
+    ));
+    let age = match info.fetch_date {
+        Some(date) => match (Utc::now() - date).num_days() {
+            0 => "Today".to_string(),
+            1 => "1 day".to_string(),
+            x => format!("{x} days"),
+        },
+        None => "Unknown".to_string(),
+    };
+    col = col.push(info_row("Data age:", age));
+
+    let th = config.blacklist_threshold.max(1);
+    let (lost, blacklisted) = match si.blacklist.get(&uid) {
+        Some((_, lost)) => (*lost, *lost >= th),
+        None => (0, false),
+    };
+    col = col.push(info_row(
+        "Blacklist:",
+        match blacklisted {
+            true => format!("Blacklisted ({lost} lost)"),
+            false => format!("{lost}/{th} lost"),
+        },
+    ));
+
+    let watched = config.is_watched(server.ident.id, uid);
+    let actions = row!(
+        button("Re-crawl")
+            .padding(4)
+            .on_press(Message::CrawlPlayerNow {
+                server: server.ident.id,
+                name: info.name.clone(),
+            }),
+        button(if blacklisted {
+            "Unblacklist"
+        } else {
+            "Blacklist"
+        })
+        .padding(4)
+        .style(theme::Button::Destructive)
+        .on_press(Message::PlayerSetBlacklist {
+            ident: aid,
+            uid,
+            name: info.name.clone(),
+            blacklisted: !blacklisted,
+        }),
+        button(if watched { "Unwatch" } else { "Watch" })
+            .padding(4)
+            .style(theme::Button::Secondary)
+            .on_press(Message::WatchlistToggle {
+                server: server.ident.id,
+                uid,
+                name: info.name.clone(),
+            }),
+    )
+    .spacing(10);
+    col = col.push(actions);
+
+    let mut equipment = column!().spacing(5);
+    for item in &info.equipment {
+        let missing =
+            si.epic_mode.counts(item) && !si.scrapbook.items.contains(item);
+        equipment = equipment.push(
+            button(text(format!("{:?}: {}", item.typ, item_name(item))))
+                .style(match missing {
+                    true => theme::Button::Positive,
+                    false => theme::Button::Secondary,
+                })
+                .padding(4)
+                .width(Length::Fill),
+        );
+    }
+    col = col.push(text("Equipment").size(18));
+    col = col.push(equipment);
+
+    col = col.push(text("Past Fights").size(18));
+    let mut fights = column!().spacing(5);
+    let mut has_fights = false;
+    for entry in player
+        .history
+        .entries
+        .iter()
+        .rev()
+        .filter(|a| {
+            matches!(&a.event, HistoryEvent::Fight { uid: f_uid, .. }
+                if *f_uid == uid)
+        })
+        .take(MAX_FIGHTS)
+    {
+        has_fights = true;
+        let won = matches!(entry.event, HistoryEvent::Fight { won: true, .. });
+        fights = fights.push(
+            button(row!(
+                text(entry.event.describe()),
+                horizontal_space(),
+                text(entry.time.format("%Y-%m-%d %H:%M").to_string())
+            ))
+            .style(match won {
+                true => theme::Button::Positive,
+                false => theme::Button::Destructive,
+            })
+            .padding(4)
+            .width(Length::Fill),
+        );
+    }
+    if !has_fights {
+        fights = fights.push(text("We have not fought this player yet"));
+    }
+    col = col.push(fights);
+
+    scrollable(col).into()
+}
@@ -8,10 +8,16 @@ use iced::{
     Alignment, Element, Length,
 };
 use iced_aw::number_input;
+use num_format::ToFormattedString;
 
-use super::view_crawling;
+use super::{
+    filter::{sort_header, view_filter},
+    view_crawling,
+};
 use crate::{
-    config::Config,
+    config::{Config, SortColumn},
+    economy::DailyYield,
+    lure::avg_unit_level,
     message::Message,
     player::{AccountInfo, AccountStatus},
     server::ServerInfo,
@@ -64,20 +70,41 @@ pub fn view_underworld<'a>(
         .horizontal_alignment(Horizontal::Right),
     ));
 
-    let avg_lvl = info
-        .underworld
-        .units
-        .as_array()
-        .iter()
-        .map(|a| a.level as u64)
-        .sum::<u64>() as f32
-        / 3.0;
+    let avg_lvl = avg_unit_level(&info.underworld);
     left_col = left_col.push(row!(
         text("Avg Unit Level:").width(Length::FillPortion(1)),
         text(format!("{:.0}", avg_lvl))
             .width(Length::FillPortion(1))
             .horizontal_alignment(Horizontal::Right),
     ));
+
+    let economy_row = |label: &'a str, value: String| {
+        row!(
+            text(label).width(Length::FillPortion(1)),
+            text(value)
+                .width(Length::FillPortion(1))
+                .horizontal_alignment(Horizontal::Right),
+        )
+    };
+    let avg = player.economy.avg_per_day(7);
+    let per_day = |value: fn(&DailyYield) -> u64| {
+        avg.as_ref()
+            .map(|a| value(a).to_formatted_string(&config.num_format))
+            .unwrap_or("???".to_string())
+    };
+    left_col = left_col.push(economy_row("Souls/Day:", per_day(|a| a.souls)));
+    left_col = left_col.push(economy_row("Gold Pit/Day:", per_day(|a| a.pit)));
+    left_col = left_col
+        .push(economy_row("Lure Silver/Day:", per_day(|a| a.lure_rewards)));
+    let souls_full = match player.economy.until_souls_full() {
+        Some(x) if x <= chrono::Duration::zero() => "Full".to_string(),
+        Some(x) if x.num_hours() >= 24 => {
+            format!("{}d {}h", x.num_days(), x.num_hours() % 24)
+        }
+        Some(x) => format!("{}h {}m", x.num_hours(), x.num_minutes() % 60),
+        None => "???".to_string(),
+    };
+    left_col = left_col.push(economy_row("Souls Full In:", souls_full));
     let aid = player.ident;
     let max_lvl = number_input(info.max_level, 9999, move |nv| {
         Message::PlayerSetMaxUndergroundLvl {
@@ -123,25 +150,46 @@ pub fn view_underworld<'a>(
     left_col = left_col.push(vertical_space());
     left_col = left_col.push(view_crawling(server, config));
 
+    let header = |label, column, alignment| {
+        sort_header(aid, &info.filter_draft, true, label, column, alignment)
+    };
     let mut name_bar = column!();
     name_bar = name_bar.push(row!(
         text("")
             .width(Length::FillPortion(1))
             .horizontal_alignment(Horizontal::Center),
-        text("Level")
-            .width(Length::FillPortion(1))
-            .horizontal_alignment(Horizontal::Center),
-        text("Items")
+        header("Level", SortColumn::Level, Horizontal::Center)
+            .width(Length::FillPortion(1)),
+        header("Items", SortColumn::Items, Horizontal::Center)
+            .width(Length::FillPortion(1)),
+        text("Chance")
             .width(Length::FillPortion(1))
             .horizontal_alignment(Horizontal::Center),
-        text("Name")
-            .width(Length::FillPortion(3))
-            .horizontal_alignment(Horizontal::Left),
+        header("Name", SortColumn::Name, Horizontal::Left)
+            .width(Length::FillPortion(3)),
     ));
     let name_bar = scrollable(name_bar);
 
+    let mut targets: Vec<_> = info.best.iter().collect();
+    if let Some(sort) = info.filter.sort {
+        targets.sort_by(|a, b| {
+            let ord = match sort.column {
+                SortColumn::Level => a.level.cmp(&b.level),
+                SortColumn::Items | SortColumn::Missing => {
+                    a.equipment.len().cmp(&b.equipment.len())
+                }
+                SortColumn::Attributes => a.stats.cmp(&b.stats),
+                SortColumn::Name => a.name.cmp(&b.name),
+            };
+            match sort.descending {
+                true => ord.reverse(),
+                false => ord,
+            }
+        });
+    }
+
     let mut target_list = column!().spacing(10);
-    for v in &info.best {
+    for v in targets {
         let mut target_ident = row!()
             .align_items(Alignment::Start)
             .spacing(5)
@@ -183,11 +231,22 @@ pub fn view_underworld<'a>(
             text(v.equipment.len())
                 .width(Length::FillPortion(1))
                 .horizontal_alignment(Horizontal::Center),
+            text(match info.chances.get(&v.uid) {
+                Some(chance) => format!("{:.0}%", chance * 100.0),
+                None => "???".to_string(),
+            })
+            .width(Length::FillPortion(1))
+            .horizontal_alignment(Horizontal::Center),
             target_ident
         ));
     }
     let target_list = scrollable(target_list);
-    let right_col = column!(name_bar, target_list).spacing(10);
+    let right_col = column!(
+        view_filter(aid, &info.filter_draft, true),
+        name_bar,
+        target_list
+    )
+    .spacing(10);
 
     row!(
         left_col.width(Length::Fixed(200.0)),
@@ -0,0 +1,164 @@
+use iced::{
+    theme,
+    widget::{button, column, horizontal_space, row, scrollable, text},
+    Alignment, Element, Length,
+};
+use iced_aw::number_input;
+
+use super::{item_name, SERVER_CODE_WIDTH};
+use crate::{
+    get_server_code,
+    message::Message,
+    server::{CrawlingStatus, ServerIdent},
+    top_bar, Helper,
+};
+
+impl Helper {
+    pub fn view_watchlist(&self) -> Element<Message> {
+        let top_bar = top_bar(
+            text("Watchlist").size(20).into(),
+            Some(Message::ViewOverview),
+        );
+
+        let interval = number_input(
+            self.config.watch_interval,
+            24 * 60,
+            Message::WatchlistSetInterval,
+        )
+        .style(iced_aw::NumberInputStyles::Default);
+        let interval = row!(
+            text("Re-crawl watched players every"),
+            interval,
+            text("minutes")
+        )
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let mut players = column!().spacing(10).width(Length::Fill);
+        if self.config.watchlist.is_empty() {
+            players = players.push(text(
+                "Use the 'Watch' button in the details of a scrapbook target \
+                 to add players",
+            ));
+        }
+
+        for watched in &self.config.watchlist {
+            let server_ident = ServerIdent::new(&watched.server);
+            let server = self.servers.get(&server_ident.id);
+
+            let info = server.and_then(|a| match &a.crawling {
+                CrawlingStatus::Crawling { player_info, .. } => {
+                    player_info.get(&watched.uid)
+                }
+                _ => None,
+            });
+
+            let level = info
+                .map(|a| a.level.to_string())
+                .unwrap_or("???".to_string());
+
+            let header = row!(
+                text(get_server_code(&server_ident.url))
+                    .width(SERVER_CODE_WIDTH),
+                text(&watched.name).width(Length::Fill),
+                text(format!("Level {level}")),
+                button("Remove")
+                    .padding(4)
+                    .style(theme::Button::Destructive)
+                    .on_press(Message::WatchlistToggle {
+                        server: server_ident.id,
+                        uid: watched.uid,
+                        name: watched.name.clone(),
+                    }),
+            )
+            .spacing(10)
+            .align_items(Alignment::Center);
+
+            let mut entry = column!(header).spacing(5);
+
+            // Highlight everything, that any of our characters on the same
+            // server is still missing
+            if let (Some(server), Some(info)) = (server, info) {
+                let mut items = row!().spacing(5);
+                let mut any_missing = false;
+                for item in info.equipment.iter().filter(|a| a.model_id < 100) {
+                    let missing_for: Vec<_> = server
+                        .accounts
+                        .values()
+                        .filter(|a| {
+                            a.scrapbook_info.as_ref().is_some_and(|si| {
+                                !si.scrapbook.items.contains(item)
+                            })
+                        })
+                        .map(|a| titlecase::titlecase(&a.name))
+                        .collect();
+                    if missing_for.is_empty() {
+                        continue;
+                    }
+                    any_missing = true;
+                    items = items.push(
+                        button(text(format!(
+                            "{} ({})",
+                            item_name(item),
+                            missing_for.join(", ")
+                        )))
+                        .style(theme::Button::Positive)
+                        .padding(4),
+                    );
+                }
+                if any_missing {
+                    entry = entry.push(scrollable(items).direction(
+                        scrollable::Direction::Horizontal(
+                            scrollable::Properties::default(),
+                        ),
+                    ));
+                }
+            }
+            players = players.push(entry);
+        }
+
+        let mut alerts = column!().spacing(5).width(Length::Fill);
+        if !self.alerts.is_empty() {
+            alerts = alerts.push(
+                row!(
+                    text("Alerts").size(18),
+                    horizontal_space(),
+                    button("Dismiss all")
+                        .padding(4)
+                        .style(theme::Button::Destructive)
+                        .on_press(Message::WatchlistDismissAlerts)
+                )
+                .align_items(Alignment::Center),
+            );
+        }
+        for alert in self.alerts.iter().rev() {
+            let items: Vec<_> = alert.items.iter().map(item_name).collect();
+            alerts = alerts.push(
+                button(row!(
+                    text(format!(
+                        "{} equipped {} (missing for {})",
+                        alert.name,
+                        items.join(", "),
+                        alert.accounts.join(", ")
+                    ))
+                    .width(Length::Fill),
+                    text(alert.time.format("%H:%M").to_string())
+                ))
+                .style(theme::Button::Positive)
+                .padding(5)
+                .width(Length::Fill),
+            );
+        }
+
+        let content = column!(interval, alerts, scrollable(players))
+            .spacing(20)
+            .padding(20)
+            .width(Length::Fill);
+
+        column!(top_bar, content)
+            .spacing(5)
+            .height(Length::Fill)
+            .align_items(Alignment::Center)
+            .into()
+    }
+}
@@ -0,0 +1,341 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use log::{info, warn};
+use sf_api::session::PWHash;
+
+use crate::config::AccountConfig;
+
+const VAULT_FILE: &str = "credentials.vault";
+const MAGIC: &[u8; 4] = b"SFV1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// The prefix of a `pw_hash` in the config, that only references an entry
+/// in the vault
+const REF_PREFIX: &str = "vault:";
+
+/// The master password, that unlocks the vault without asking
+pub const PASSWORD_ENV: &str = "SF_VAULT_PASSWORD";
+/// A file descriptor, from which the master password is read. Useful, if the
+/// password should not show up in the environment of the process
+pub const PASSWORD_FD_ENV: &str = "SF_VAULT_PASSWORD_FD";
+
+static VAULT: Mutex<Option<Vault>> = Mutex::new(None);
+
+/// Password hashes, that are encrypted with a key derived from the master
+/// password
+struct Vault {
+    key: Key,
+    salt: [u8; SALT_LEN],
+    entries: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+pub enum VaultError {
+    WrongPassword,
+    Invalid,
+    /// The config references credentials in a vault, that does not exist
+    Missing,
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for VaultError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VaultError::WrongPassword => f.write_str("Wrong master password"),
+            VaultError::Invalid => f.write_str("The vault is corrupted"),
+            VaultError::Missing => f.write_fmt(format_args!(
+                "The credentials are stored in {VAULT_FILE}, but the file is \
+                 missing"
+            )),
+            VaultError::Io(e) => {
+                f.write_fmt(format_args!("Could not access the vault: {e}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for VaultError {}
+
+impl From<std::io::Error> for VaultError {
+    fn from(value: std::io::Error) -> Self {
+        VaultError::Io(value)
+    }
+}
+
+impl Vault {
+    fn derive_key(
+        password: &str,
+        salt: &[u8; SALT_LEN],
+    ) -> Result<Key, VaultError> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(password.as_bytes(), salt, &mut key)
+            .map_err(|_| VaultError::Invalid)?;
+        Ok(key)
+    }
+
+    fn create(password: &str) -> Result<Self, VaultError> {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Ok(Self {
+            key: Self::derive_key(password, &salt)?,
+            salt,
+            entries: BTreeMap::new(),
+        })
+    }
+
+    fn open(password: &str) -> Result<Self, VaultError> {
+        let data = std::fs::read(VAULT_FILE)?;
+        let Some(data) = data.strip_prefix(MAGIC) else {
+            return Err(VaultError::Invalid);
+        };
+        if data.len() < SALT_LEN {
+            return Err(VaultError::Invalid);
+        }
+        let (salt, data) = data.split_at(SALT_LEN);
+        let salt: [u8; SALT_LEN] =
+            salt.try_into().map_err(|_| VaultError::Invalid)?;
+        let key = Self::derive_key(password, &salt)?;
+        let data = decrypt(&key, data).ok_or(VaultError::WrongPassword)?;
+        let entries =
+            serde_json::from_slice(&data).map_err(|_| VaultError::Invalid)?;
+        Ok(Self { key, salt, entries })
+    }
+
+    fn save(&self) -> Result<(), VaultError> {
+        let data = serde_json::to_vec(&self.entries)
+            .map_err(|_| VaultError::Invalid)?;
+        let data = encrypt(&self.key, &data).ok_or(VaultError::Invalid)?;
+        let mut out = MAGIC.to_vec();
+        out.extend(self.salt);
+        out.extend(data);
+        std::fs::write(VAULT_FILE, out)?;
+        Ok(())
+    }
+}
+
+pub fn encrypt(key: &Key, data: &[u8]) -> Option<Vec<u8>> {
+    let cipher = ChaCha20Poly1305::new(key);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let mut res = nonce.to_vec();
+    res.extend(cipher.encrypt(&nonce, data).ok()?);
+    Some(res)
+}
+
+pub fn decrypt(key: &Key, data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, data) = data.split_at(NONCE_LEN);
+    let cipher = ChaCha20Poly1305::new(key);
+    cipher.decrypt(Nonce::from_slice(nonce), data).ok()
+}
+
+/// Encrypts the data with a key derived from the password. The salt is
+/// stored in front of the result, so only the password is needed to unseal it
+pub fn seal(password: &str, data: &[u8]) -> Result<Vec<u8>, VaultError> {
+    let mut salt = [0; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = Vault::derive_key(password, &salt)?;
+    let mut res = salt.to_vec();
+    res.extend(encrypt(&key, data).ok_or(VaultError::Invalid)?);
+    Ok(res)
+}
+
+pub fn unseal(password: &str, data: &[u8]) -> Result<Vec<u8>, VaultError> {
+    if data.len() < SALT_LEN {
+        return Err(VaultError::Invalid);
+    }
+    let (salt, data) = data.split_at(SALT_LEN);
+    let salt: [u8; SALT_LEN] =
+        salt.try_into().map_err(|_| VaultError::Invalid)?;
+    let key = Vault::derive_key(password, &salt)?;
+    decrypt(&key, data).ok_or(VaultError::WrongPassword)
+}
+
+/// Encrypts a key with the key of the unlocked vault, so that it can only be
+/// used after unlocking the vault again
+pub fn wrap_key(key: &[u8]) -> Option<Vec<u8>> {
+    let lock = VAULT.lock().unwrap();
+    encrypt(&lock.as_ref()?.key, key)
+}
+
+/// Decrypts a key, that was wrapped with [`wrap_key`]
+pub fn unwrap_key(data: &[u8]) -> Option<Vec<u8>> {
+    let lock = VAULT.lock().unwrap();
+    decrypt(&lock.as_ref()?.key, data)
+}
+
+pub fn exists() -> bool {
+    std::path::Path::new(VAULT_FILE).exists()
+}
+
+pub fn is_unlocked() -> bool {
+    VAULT.lock().unwrap().is_some()
+}
+
+/// Unlocks the existing vault
+pub fn unlock(password: &str) -> Result<(), VaultError> {
+    if !exists() {
+        return Err(VaultError::Missing);
+    }
+    *VAULT.lock().unwrap() = Some(Vault::open(password)?);
+    Ok(())
+}
+
+/// Creates a new, empty vault and unlocks it
+pub fn create(password: &str) -> Result<(), VaultError> {
+    let vault = Vault::create(password)?;
+    vault.save()?;
+    info!("Created a new credential vault");
+    *VAULT.lock().unwrap() = Some(vault);
+    Ok(())
+}
+
+/// Unlocks the vault with the password from the environment. Does nothing,
+/// if there is no vault, or no password has been given
+pub fn unlock_from_env() -> Result<(), VaultError> {
+    if !exists() {
+        return Ok(());
+    }
+    match password_from_env() {
+        Some(password) => unlock(&password),
+        None => Ok(()),
+    }
+}
+
+/// Stops using the vault. The credentials have to be resolved before this is
+/// called, so that `write` can save them in plain text. The vault is only
+/// removed, once that succeeded
+pub fn disable<E>(write: impl FnOnce() -> Result<(), E>) -> Result<(), E> {
+    let vault = VAULT.lock().unwrap().take();
+    if let Err(e) = write() {
+        *VAULT.lock().unwrap() = vault;
+        return Err(e);
+    }
+    if let Err(e) = std::fs::remove_file(VAULT_FILE) {
+        warn!("Could not remove the vault: {e}");
+    }
+    Ok(())
+}
+
+/// Reads the master password from the environment, or the file descriptor
+/// given in it
+fn password_from_env() -> Option<String> {
+    secret_from_env(PASSWORD_ENV, PASSWORD_FD_ENV)
+}
+
+/// Reads a secret from the `env` variable, or from the file descriptor given
+/// in the `fd_env` variable, so that it does not show up in the arguments of
+/// the process
+pub fn secret_from_env(env: &str, fd_env: &str) -> Option<String> {
+    if let Ok(secret) = std::env::var(env) {
+        return Some(secret);
+    }
+    let fd: i32 = std::env::var(fd_env).ok()?.parse().ok()?;
+    read_fd(fd, fd_env)
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32, _fd_env: &str) -> Option<String> {
+    use std::{io::Read, os::fd::FromRawFd};
+    // SAFETY: The fd has been handed to us explicitly & is only read once
+    let mut file = unsafe { std::fs::File::from_raw_fd(fd) };
+    let mut password = String::new();
+    if let Err(e) = file.read_to_string(&mut password) {
+        warn!("Could not read the secret from fd {fd}: {e}");
+        return None;
+    }
+    Some(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32, fd_env: &str) -> Option<String> {
+    warn!("{fd_env} is only supported on unix");
+    None
+}
+
+fn pw_hash(acc: &mut AccountConfig) -> Option<&mut PWHash> {
+    match acc {
+        AccountConfig::Regular { pw_hash, .. }
+        | AccountConfig::SF { pw_hash, .. } => Some(pw_hash),
+        AccountConfig::SSO { .. } => None,
+    }
+}
+
+/// Checks if the password hash is only a reference to an entry in the vault
+pub fn is_reference(pw_hash: &PWHash) -> bool {
+    pw_hash.get().starts_with(REF_PREFIX)
+}
+
+/// Checks if any account still only has a reference to the vault instead of
+/// the actual password hash
+pub fn has_references(accounts: &[AccountConfig]) -> bool {
+    accounts.iter().any(|acc| match acc {
+        AccountConfig::Regular { pw_hash, .. }
+        | AccountConfig::SF { pw_hash, .. } => is_reference(pw_hash),
+        AccountConfig::SSO { .. } => false,
+    })
+}
+
+/// Replaces the vault references of the accounts with the password hashes
+/// from the unlocked vault
+pub fn resolve(accounts: &mut [AccountConfig]) {
+    let lock = VAULT.lock().unwrap();
+    let Some(vault) = lock.as_ref() else {
+        return;
+    };
+    for acc in accounts {
+        let Some(hash) = pw_hash(acc) else {
+            continue;
+        };
+        // The reference is used instead of the key of the account, so that
+        // entries, which were stored under an older key, are still found
+        let Some(id) = hash.get().strip_prefix(REF_PREFIX).map(str::to_string)
+        else {
+            continue;
+        };
+        match vault.entries.get(&id) {
+            Some(entry) => *hash = PWHash::from_hash(entry.clone()),
+            None => warn!("The vault has no entry for {id}"),
+        }
+    }
+}
+
+/// Moves the password hashes of the accounts into the vault. Returns the
+/// accounts, that only contain references, if the vault is in use
+pub fn store(
+    accounts: &[AccountConfig],
+) -> Result<Option<Vec<AccountConfig>>, VaultError> {
+    let mut lock = VAULT.lock().unwrap();
+    let Some(vault) = lock.as_mut() else {
+        return Ok(None);
+    };
+    let mut accounts = accounts.to_vec();
+    let mut entries = BTreeMap::new();
+    for acc in &mut accounts {
+        let id = acc.key();
+        let Some(hash) = pw_hash(acc) else {
+            continue;
+        };
+        let value = match hash.get().strip_prefix(REF_PREFIX) {
+            // The entry could not be resolved, so we keep what we have
+            Some(old_id) => vault.entries.get(old_id).cloned(),
+            None => Some(hash.get().to_string()),
+        };
+        if let Some(value) = value {
+            entries.insert(id.clone(), value);
+        }
+        *hash = PWHash::from_hash(format!("{REF_PREFIX}{id}"));
+    }
+    if entries != vault.entries {
+        vault.entries = entries;
+        vault.save()?;
+    }
+    Ok(Some(accounts))
+}
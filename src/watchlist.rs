@@ -0,0 +1,73 @@
+use chrono::{DateTime, Local};
+use sf_api::gamestate::unlockables::EquipmentIdent;
+
+use crate::{player::AccountInfo, CharacterInfo, ServerID};
+
+/// A watched player equipped items, that at least one of our characters is
+/// still missing
+#[derive(Debug, Clone)]
+pub struct WatchAlert {
+    pub time: DateTime<Local>,
+    pub server: ServerID,
+    pub uid: u32,
+    pub name: String,
+    pub items: Vec<EquipmentIdent>,
+    /// The names of all of our characters, that are missing any of the items
+    pub accounts: Vec<String>,
+}
+
+impl WatchAlert {
+    /// Compares the new info of a watched player with the one we had before
+    /// and creates an alert, if the player equipped something new, that one
+    /// of the given accounts does not have in their scrapbook
+    pub fn check<'a>(
+        server: ServerID,
+        old: Option<&CharacterInfo>,
+        new: &CharacterInfo,
+        accounts: impl Iterator<Item = &'a AccountInfo>,
+    ) -> Option<WatchAlert> {
+        let new_items: Vec<_> = new
+            .equipment
+            .iter()
+            .filter(|a| a.model_id < 100)
+            .filter(|a| old.map_or(true, |old| !old.equipment.contains(a)))
+            .collect();
+        if new_items.is_empty() {
+            return None;
+        }
+
+        let mut items: Vec<EquipmentIdent> = vec![];
+        let mut names = vec![];
+        for account in accounts {
+            let Some(si) = &account.scrapbook_info else {
+                continue;
+            };
+            let mut is_missing = false;
+            for item in &new_items {
+                if si.scrapbook.items.contains(item) {
+                    continue;
+                }
+                is_missing = true;
+                if !items.contains(item) {
+                    items.push(**item);
+                }
+            }
+            if is_missing {
+                names.push(account.name.clone());
+            }
+        }
+
+        if items.is_empty() {
+            return None;
+        }
+
+        Some(WatchAlert {
+            time: Local::now(),
+            server,
+            uid: new.uid,
+            name: new.name.clone(),
+            items,
+            accounts: names,
+        })
+    }
+}